@@ -2,7 +2,9 @@ use crate::bitcoin;
 use crate::bitcoin::BlockHash;
 use crate::chain_config::{Auth, ConfigFile};
 use crate::error::Error;
+use crate::json::currency::*;
 use crate::json::identity::*;
+use crate::json::trust::*;
 use crate::json::*;
 use serde_json::{json, Value};
 
@@ -69,9 +71,25 @@ fn empty_arr() -> serde_json::Value {
 }
 
 /// Shorthand for an empty serde_json object.
-// fn empty_obj() -> serde_json::Value {
-//     serde_json::Value::Object(Default::default())
-// }
+fn empty_obj() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+/// The code and message of the error `getspentinfo` throws when it finds no spending transaction.
+/// verusd throws the same error when it runs without `-spentindex`, as the index lookup then
+/// fails for every output.
+const SPENT_INFO_ERROR: (i32, &str) = (-5, "Unable to get spent info");
+
+fn spent_info_error(e: Error, txid: bitcoin::Txid, index: u32) -> Error {
+    match e {
+        Error::JsonRPC(jsonrpc::Error::Rpc(ref rpc_error))
+            if (rpc_error.code, rpc_error.message.as_str()) == SPENT_INFO_ERROR =>
+        {
+            Error::SpentInfoUnavailable(txid, index)
+        }
+        e => e,
+    }
+}
 
 /// Handle default values in the argument list
 ///
@@ -236,11 +254,45 @@ struct AddressList {
     pub addresses: Vec<Address>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ListCurrenciesQueryObject {
-    pub systemtype: String,
+/// Filters for `listcurrencies`. All filters are optional; an empty query lists all currencies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListCurrenciesQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launchstate: Option<LaunchStateFilter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub systemtype: Option<SystemType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fromsystem: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converter: Option<Vec<String>>,
+}
+
+impl ListCurrenciesQuery {
+    pub fn launchstate(mut self, launchstate: LaunchStateFilter) -> Self {
+        self.launchstate = Some(launchstate);
+        self
+    }
+
+    pub fn systemtype(mut self, systemtype: SystemType) -> Self {
+        self.systemtype = Some(systemtype);
+        self
+    }
+
+    /// Lists currencies from another system instead of the local chain.
+    pub fn fromsystem(mut self, system: &str) -> Self {
+        self.fromsystem = Some(system.to_string());
+        self
+    }
+
+    /// Only lists fractional currencies that have all of these currencies as reserves.
+    pub fn converter(mut self, reserves: &[&str]) -> Self {
+        self.converter = Some(reserves.iter().map(|r| r.to_string()).collect());
+        self
+    }
 }
 
+/// One output of `sendcurrency`. Only `amount` and `address` are required; the other fields are
+/// set with the builder methods.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SendCurrencyOutput<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -248,8 +300,30 @@ pub struct SendCurrencyOutput<'a> {
     #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
     pub amount: Amount,
     pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub convertto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub via: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exportto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exportid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exportcurrency: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feecurrency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refundto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preconvert: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burn: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mintnew: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub addconversionfees: Option<bool>,
 }
 
 impl<'a> SendCurrencyOutput<'a> {
@@ -266,8 +340,78 @@ impl<'a> SendCurrencyOutput<'a> {
             address: address.to_string(),
             convertto: convertto.map(|c| c.to_string()),
             via: via.map(|via| via.to_string()),
+            exportto: None,
+            exportid: None,
+            exportcurrency: None,
+            feecurrency: None,
+            refundto: None,
+            memo: None,
+            preconvert: None,
+            burn: None,
+            mintnew: None,
+            addconversionfees: None,
         }
     }
+
+    /// The chain or system to export this output to.
+    pub fn exportto(mut self, system: &str) -> Self {
+        self.exportto = Some(system.to_string());
+        self
+    }
+
+    /// Exports the full identity of a cross-chain ID destination to the destination system.
+    pub fn exportid(mut self, exportid: bool) -> Self {
+        self.exportid = Some(exportid);
+        self
+    }
+
+    /// Exports the full currency definition to the destination system.
+    pub fn exportcurrency(mut self, exportcurrency: bool) -> Self {
+        self.exportcurrency = Some(exportcurrency);
+        self
+    }
+
+    /// The currency in this wallet to pay the fee with.
+    pub fn feecurrency(mut self, currency: &str) -> Self {
+        self.feecurrency = Some(currency.to_string());
+        self
+    }
+
+    /// Where refunds of pre-conversions go. Defaults to the from address.
+    pub fn refundto(mut self, destination: &str) -> Self {
+        self.refundto = Some(destination.to_string());
+        self
+    }
+
+    /// A message to include when sending to a z-address.
+    pub fn memo(mut self, memo: &str) -> Self {
+        self.memo = Some(memo.to_string());
+        self
+    }
+
+    /// Converts at the launch price; only valid before the start block of the currency.
+    pub fn preconvert(mut self, preconvert: bool) -> Self {
+        self.preconvert = Some(preconvert);
+        self
+    }
+
+    /// Destroys the currency and removes it from the supply. Only valid for tokens.
+    pub fn burn(mut self, burn: bool) -> Self {
+        self.burn = Some(burn);
+        self
+    }
+
+    /// Mints new currency when sending from the ID of a centralized currency.
+    pub fn mintnew(mut self, mintnew: bool) -> Self {
+        self.mintnew = Some(mintnew);
+        self
+    }
+
+    /// Adds the conversion fees on top of `amount`, instead of taking them out of it.
+    pub fn addconversionfees(mut self, addconversionfees: bool) -> Self {
+        self.addconversionfees = Some(addconversionfees);
+        self
+    }
 }
 
 impl<'a> From<(&Address, &Amount)> for SendCurrencyOutput<'a> {
@@ -276,6 +420,63 @@ impl<'a> From<(&Address, &Amount)> for SendCurrencyOutput<'a> {
     }
 }
 
+/// One conversion to estimate with `estimateconversion`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EstimateConversionRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+    pub amount: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convertto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preconvert: Option<bool>,
+}
+
+impl EstimateConversionRequest {
+    pub fn new(currency: Option<&str>, amount: &Amount, convertto: Option<&str>) -> Self {
+        EstimateConversionRequest {
+            currency: currency.map(|c| c.to_string()),
+            amount: *amount,
+            convertto: convertto.map(|c| c.to_string()),
+            via: None,
+            preconvert: None,
+        }
+    }
+
+    pub fn via(mut self, via: &str) -> Self {
+        self.via = Some(via.to_string());
+        self
+    }
+
+    pub fn preconvert(mut self, preconvert: bool) -> Self {
+        self.preconvert = Some(preconvert);
+        self
+    }
+}
+
+/// Quotes the conversion that a `sendcurrency` output would do.
+impl<'a> From<&SendCurrencyOutput<'a>> for EstimateConversionRequest {
+    fn from(output: &SendCurrencyOutput<'a>) -> Self {
+        EstimateConversionRequest {
+            currency: output.currency.map(|c| c.to_string()),
+            amount: output.amount,
+            convertto: output.convertto.clone(),
+            via: output.via.clone(),
+            preconvert: output.preconvert,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EstimateConversionResults {
+    Many(Vec<EstimateConversionResult>),
+    One(Box<EstimateConversionResult>),
+}
+
 // This trait is to be implemented by an implementation of a client, and only the `call` method
 // is to be implemented.
 // All the other methods are methods that a client can call, which in turn do RPCs to the coin daemon.
@@ -301,6 +502,145 @@ pub trait RpcApi: Sized {
         self.call("getcurrency", &[into_json(currency)?])
     }
 
+    /// Validates the definition locally and returns the signed definition transaction, which still
+    /// needs to be sent with `sendrawtransaction`. PBaaS chains and gateways can define their
+    /// converter currency along with them.
+    fn define_currency(
+        &self,
+        definition: &CurrencyDefinitionBuilder,
+        gateway_converter: Option<&CurrencyDefinitionBuilder>,
+    ) -> Result<DefineCurrencyResult> {
+        definition.validate()?;
+        if let Some(converter) = gateway_converter {
+            converter.validate()?;
+        }
+
+        let mut args = [into_json(definition)?, opt_into_json(gateway_converter)?];
+        self.call("definecurrency", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Lists the exports from this chain to `system`, with the reserve transfers they contain.
+    fn get_exports(
+        &self,
+        system: &str,
+        start_height: Option<u64>,
+        end_height: Option<u64>,
+    ) -> Result<Vec<GetExportsResult>> {
+        let mut args = [
+            into_json(system)?,
+            opt_into_json(start_height)?,
+            opt_into_json(end_height)?,
+        ];
+        let defaults = [into_json(0)?, null()];
+
+        self.call("getexports", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Lists the imports into this chain from `system`, with the reserve transfers they contain.
+    fn get_imports(
+        &self,
+        system: &str,
+        start_height: Option<u64>,
+        end_height: Option<u64>,
+    ) -> Result<Vec<GetImportsResult>> {
+        let mut args = [
+            into_json(system)?,
+            opt_into_json(start_height)?,
+            opt_into_json(end_height)?,
+        ];
+        let defaults = [into_json(0)?, null()];
+
+        self.call("getimports", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Lists the reserve transfers to `system` that are not exported yet.
+    fn get_pending_transfers(
+        &self,
+        system: &str,
+    ) -> Result<Vec<GetRawTransactionScriptPubKeyReserveTransfer>> {
+        self.call("getpendingtransfers", &[into_json(system)?])
+    }
+
+    fn get_last_import_from(&self, system: &str) -> Result<GetLastImportFromResult> {
+        self.call("getlastimportfrom", &[into_json(system)?])
+    }
+
+    /// Returns the notarizations of `currency` that this chain knows about, with the forks
+    /// between them.
+    fn get_notarization_data(
+        &self,
+        currency: &str,
+        get_evidence: Option<bool>,
+    ) -> Result<GetNotarizationDataResult> {
+        let mut args = [into_json(currency)?, opt_into_json(get_evidence)?];
+
+        self.call("getnotarizationdata", handle_defaults(&mut args, &[null()]))
+    }
+
+    /// Determines which of the given proof roots of this chain is the best to notarize, along
+    /// with the current proof roots and the states of `currencies`.
+    fn get_best_proof_root(
+        &self,
+        proof_roots: &[ProofRoot],
+        currencies: &[&str],
+        last_confirmed: Option<i64>,
+    ) -> Result<GetBestProofRootResult> {
+        let mut request = serde_json::Map::new();
+        request.insert(String::from("proofroots"), into_json(proof_roots)?);
+        if !currencies.is_empty() {
+            request.insert(String::from("currencies"), into_json(currencies)?);
+        }
+        if let Some(last_confirmed) = last_confirmed {
+            request.insert(String::from("lastconfirmed"), into_json(last_confirmed)?);
+        }
+
+        self.call("getbestproofroot", &[request.into()])
+    }
+
+    fn get_launch_info(&self, currency: &str) -> Result<GetLaunchInfoResult> {
+        self.call("getlaunchinfo", &[into_json(currency)?])
+    }
+
+    /// Returns the evidence for each of the challenge requests, in the same order.
+    fn get_notarization_proofs(
+        &self,
+        challenges: &[NotarizationProofRequest],
+    ) -> Result<GetNotarizationProofsResult> {
+        self.call("getnotarizationproofs", &[into_json(challenges)?])
+    }
+
+    fn get_reserve_deposits(&self, currency: &str) -> Result<ReserveDeposits> {
+        self.call("getreservedeposits", &[into_json(currency)?])
+    }
+
+    /// Returns the wallet's currency ratings, for all rated currencies if `currencies` is empty.
+    fn get_currency_trust(&self, currencies: &[&str]) -> Result<GetCurrencyTrustResult> {
+        let mut args = [opt_into_json(
+            (!currencies.is_empty()).then_some(currencies),
+        )?];
+
+        self.call("getcurrencytrust", handle_defaults(&mut args, &[null()]))
+    }
+
+    fn set_currency_trust(&self, request: &SetTrustRequest) -> Result<()> {
+        self.call::<Value>("setcurrencytrust", &[into_json(request)?])
+            .map(|_| ())
+    }
+
+    /// Returns the wallet's identity ratings, for all rated identities if `identities` is empty.
+    fn get_identity_trust(&self, identities: &[&str]) -> Result<GetIdentityTrustResult> {
+        let mut args = [opt_into_json(
+            (!identities.is_empty()).then_some(identities),
+        )?];
+
+        self.call("getidentitytrust", handle_defaults(&mut args, &[null()]))
+    }
+
+    fn set_identity_trust(&self, request: &SetTrustRequest) -> Result<()> {
+        self.call::<Value>("setidentitytrust", &[into_json(request)?])
+            .map(|_| ())
+    }
+
     fn get_currency_converters(
         &self,
         currency: impl AsRef<str>,
@@ -311,17 +651,17 @@ pub trait RpcApi: Sized {
     fn get_currency_state(
         &self,
         currency: &str,
-        range: Option<&str>,
+        range: Option<&CurrencyStateRange>,
         express_in: Option<&str>,
     ) -> Result<Vec<GetCurrencyStateResult>> {
-        self.call(
-            "getcurrencystate",
-            &[
-                into_json(currency)?,
-                opt_into_json(range)?,
-                opt_into_json(express_in)?,
-            ],
-        )
+        let mut args = [
+            into_json(currency)?,
+            opt_into_json(range)?,
+            opt_into_json(express_in)?,
+        ];
+        let defaults = [into_json("")?, null()];
+
+        self.call("getcurrencystate", handle_defaults(&mut args, &defaults))
     }
 
     fn get_currency_balance(
@@ -362,16 +702,38 @@ pub trait RpcApi: Sized {
         self.call("getidentitieswithaddress", &[input])
     }
 
-    fn list_currencies(&self, system_type: Option<&str>) -> Result<ListCurrenciesResult> {
-        if let Some(systemtype) = system_type {
-            self.call(
-                "listcurrencies",
-                &[into_json(ListCurrenciesQueryObject {
-                    systemtype: String::from(systemtype),
-                })?],
-            )
-        } else {
-            self.call("listcurrencies", &[])
+    /// Lists currencies matching the query, optionally only those defined between two heights.
+    fn list_currencies(
+        &self,
+        query: Option<&ListCurrenciesQuery>,
+        start_block: Option<u64>,
+        end_block: Option<u64>,
+    ) -> Result<ListCurrenciesResult> {
+        let mut args = [
+            opt_into_json(query)?,
+            opt_into_json(start_block)?,
+            opt_into_json(end_block)?,
+        ];
+        let defaults = [empty_obj(), into_json(0)?, null()];
+
+        self.call("listcurrencies", handle_defaults(&mut args, &defaults))
+    }
+
+    fn estimate_conversion(
+        &self,
+        conversion: &EstimateConversionRequest,
+    ) -> Result<EstimateConversionResult> {
+        self.call("estimateconversion", &[into_json(conversion)?])
+    }
+
+    /// Estimates several conversions at once. Results are in the order of `conversions`.
+    fn estimate_conversions(
+        &self,
+        conversions: &[EstimateConversionRequest],
+    ) -> Result<Vec<EstimateConversionResult>> {
+        match self.call("estimateconversion", &[into_json(conversions)?])? {
+            EstimateConversionResults::Many(results) => Ok(results),
+            EstimateConversionResults::One(result) => Ok(vec![*result]),
         }
     }
 
@@ -382,6 +744,11 @@ pub trait RpcApi: Sized {
         self.call("z_getoperationstatus", &[into_json(opid)?])
     }
 
+    /// Like `z_get_operation_status`, but only returns finished operations and removes them from memory.
+    fn z_get_operation_result(&self, opid: Vec<&str>) -> Result<Vec<ZOperationStatusResult>> {
+        self.call("z_getoperationresult", &[into_json(opid)?])
+    }
+
     fn z_shield_coinbase(
         &self,
         from: &str,
@@ -405,10 +772,38 @@ pub trait RpcApi: Sized {
         &self,
         from: &str,
         outputs: Vec<SendCurrencyOutput>,
-        _minconf: Option<u16>,
-        _fee_amount: Option<f64>,
+        minconf: Option<u16>,
+        fee_amount: Option<Amount>,
     ) -> Result<String> {
-        self.call("sendcurrency", &[from.into(), into_json(outputs)?])
+        let mut args = [
+            from.into(),
+            into_json(outputs)?,
+            opt_into_json(minconf)?,
+            opt_into_json(fee_amount.map(|fee| fee.as_vrsc()))?,
+        ];
+        let defaults = [into_json(1)?, into_json(0.0001)?];
+
+        self.call("sendcurrency", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Like `send_currency`, but returns the unsigned transaction template instead of sending it.
+    fn send_currency_template(
+        &self,
+        from: &str,
+        outputs: Vec<SendCurrencyOutput>,
+        minconf: Option<u16>,
+        fee_amount: Option<Amount>,
+    ) -> Result<SendCurrencyResult> {
+        let mut args = [
+            from.into(),
+            into_json(outputs)?,
+            opt_into_json(minconf)?,
+            opt_into_json(fee_amount.map(|fee| fee.as_vrsc()))?,
+            true.into(),
+        ];
+        let defaults = [into_json(1)?, into_json(0.0001)?, null()];
+
+        self.call("sendcurrency", handle_defaults(&mut args, &defaults))
     }
 
     fn validate_address<S>(&self, address: S) -> Result<ValidateAddress>
@@ -426,6 +821,42 @@ pub trait RpcApi: Sized {
         self.call("getaddressutxos", &[into_json(AddressList { addresses })?])
     }
 
+    /// The unspent outputs of `addresses`, with the block they were read at, so that they can
+    /// be matched against the chain.
+    fn get_address_utxos_with_chain_info(
+        &self,
+        addresses: &[&Address],
+    ) -> Result<AddressUtxosWithChainInfo> {
+        let input = json!({
+            "addresses": into_json(addresses)?,
+            "chainInfo": true
+        });
+
+        self.call("getaddressutxos", &[input])
+    }
+
+    /// The txids that involve `addresses`, optionally only between the `start` and `end` heights
+    /// of `range`, inclusive.
+    fn get_address_txids(
+        &self,
+        addresses: &[&Address],
+        range: Option<(u64, u64)>,
+    ) -> Result<Vec<bitcoin::Txid>> {
+        let mut input = json!({ "addresses": into_json(addresses)? });
+        if let Some((start, end)) = range {
+            input["start"] = into_json(start)?;
+            input["end"] = into_json(end)?;
+        }
+
+        self.call("getaddresstxids", &[input])
+    }
+
+    fn get_address_mempool(&self, addresses: &[&Address]) -> Result<Vec<AddressMempoolDelta>> {
+        let input = json!({ "addresses": into_json(addresses)? });
+
+        self.call("getaddressmempool", &[input])
+    }
+
     fn get_address_deltas(
         &self,
         addresses: &[&Address],
@@ -441,8 +872,11 @@ pub trait RpcApi: Sized {
         self.call("getaddressdeltas", &[input])
     }
 
-    fn get_address_balance(&self, addresses: Vec<Address>) -> Result<Vec<AddressUtxos>> {
-        self.call("getaddressdeltas", &[into_json(AddressList { addresses })?])
+    fn get_address_balance(&self, addresses: Vec<Address>) -> Result<AddressBalance> {
+        self.call(
+            "getaddressbalance",
+            &[into_json(AddressList { addresses })?],
+        )
     }
     // Identity
 
@@ -598,9 +1032,24 @@ pub trait RpcApi: Sized {
     }
 
     // Marketplace
-    fn closeoffers(&self) -> Result<()> {
-        unimplemented!()
-    } // ('["offer1_txid", "offer2_txid", ...]') (transparentorprivatefundsdestination) (privatefundsdestination)
+
+    /// Closes the given offers made from this wallet, returning the funds to the given destinations.
+    /// Without a destination, funds are returned to an address in this wallet.
+    fn close_offers(
+        &self,
+        offer_txids: &[bitcoin::Txid],
+        transparent_or_private_destination: Option<&str>,
+        private_destination: Option<&str>,
+    ) -> Result<()> {
+        let mut args = [
+            into_json(offer_txids)?,
+            opt_into_json(transparent_or_private_destination)?,
+            opt_into_json(private_destination)?,
+        ];
+        let defaults = [into_json("")?, null()];
+
+        self.call("closeoffers", handle_defaults(&mut args, &defaults))
+    }
 
     fn get_offers(
         &self,
@@ -618,15 +1067,56 @@ pub trait RpcApi: Sized {
         )
     }
 
-    fn listopenoffers(&self) -> Result<()> {
-        unimplemented!()
-    } // (unexpired) (expired)'
-    fn makeoffer(&self) -> Result<()> {
-        unimplemented!()
-    } // fromaddress '{"changeaddress":"transparentoriaddress", "expiryheight":blockheight, "offer":{"currency":"anycurrency", "amount":...} | {"identity":"idnameoriaddress",...}', "for":{"address":..., "currency":"anycurrency", "amount":...} | {"name":"identityforswap","parent":"parentid","primaryaddresses":["R-address(s)"],"minimumsignatures":1,...}}' (returntx) (feeamount)
-    fn takeoffer(&self) -> Result<()> {
-        unimplemented!()
-    } // fromaddress '{"txid":"txid" | "tx":"hextx", "changeaddress":"transparentoriaddress", "deliver":"fullidnameoriaddresstodeliver" | {"currency":"currencynameorid","amount":n}, "accept":{"address":"addressorid","currency":"currencynameorid","amount"} | {identitydefinition}}' (returntx) (feeamount)
+    /// Lists the offers made from this wallet. Both unexpired and expired offers are included by default.
+    fn list_open_offers(
+        &self,
+        unexpired: Option<bool>,
+        expired: Option<bool>,
+    ) -> Result<Vec<OpenOffer>> {
+        let mut args = [opt_into_json(unexpired)?, opt_into_json(expired)?];
+        let defaults = [into_json(true)?, into_json(true)?];
+
+        self.call("listopenoffers", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Posts an offer on-chain. With `return_tx` set, the (partial) transaction is returned as hex
+    /// instead of being broadcast.
+    fn make_offer(
+        &self,
+        from: &str,
+        offer: &MakeOfferRequest,
+        return_tx: Option<bool>,
+        fee_amount: Option<Amount>,
+    ) -> Result<MakeOfferResult> {
+        let mut args = [
+            from.into(),
+            into_json(offer)?,
+            opt_into_json(return_tx)?,
+            opt_into_json(fee_amount.map(|fee| fee.as_vrsc()))?,
+        ];
+        let defaults = [into_json(false)?, null()];
+
+        self.call("makeoffer", handle_defaults(&mut args, &defaults))
+    }
+
+    /// Takes an offer. Returns the txid, or the hex of the transaction if `return_tx` is set.
+    fn take_offer(
+        &self,
+        from: &str,
+        offer: &TakeOfferRequest,
+        return_tx: Option<bool>,
+        fee_amount: Option<Amount>,
+    ) -> Result<String> {
+        let mut args = [
+            from.into(),
+            into_json(offer)?,
+            opt_into_json(return_tx)?,
+            opt_into_json(fee_amount.map(|fee| fee.as_vrsc()))?,
+        ];
+        let defaults = [into_json(false)?, null()];
+
+        self.call("takeoffer", handle_defaults(&mut args, &defaults))
+    }
 
     /// Get a block, based on its hash (later on: and height todo).
     fn get_block(&self, hash: &bitcoin::BlockHash, verbosity: u8) -> Result<Block> {
@@ -646,6 +1136,13 @@ pub trait RpcApi: Sized {
         // into_json()
     }
 
+    /// Gets a block as serialized by the daemon and decodes it.
+    fn get_block_raw(&self, hash: &bitcoin::BlockHash) -> Result<raw::Block> {
+        let hex: String = self.call("getblock", &[into_json(hash)?, 0.into()])?;
+
+        Ok(raw::deserialize_hex(&hex)?)
+    }
+
     fn get_block_by_height(&self, height: u64, verbosity: u8) -> Result<Block> {
         // let val = serde_json::to_value(hash)?;
 
@@ -718,19 +1215,15 @@ pub trait RpcApi: Sized {
         self.call("getrawmempool", &[into_json(true)?])
     }
 
-    fn get_spent_info(&self, _txid: bitcoin::Txid, _index: u32) -> Result<SpentInfoResult> {
-        // let mut hashmap: HashMap<String, String> = HashMap::new();
-        // hashmap.insert(String::from("txid"), txid.to_string());
-        // hashmap.insert(String::from("index"), index.to_string());
-
-        // let args = [into_json(hashmap)?];
+    /// Where the output `index` of `txid` was spent. Fails with `SpentInfoUnavailable` if the
+    /// output is unspent, and for every output if the daemon runs without `-spentindex`.
+    fn get_spent_info(&self, txid: bitcoin::Txid, index: u32) -> Result<SpentInfoResult> {
+        let args = [json!({ "txid": txid, "index": index })];
 
-        // self.call("getspentinfo", &args)
-
-        // TODO the getspentinfo call does not work
-
-        unimplemented!()
+        self.call("getspentinfo", &args)
+            .map_err(|e| spent_info_error(e, txid, index))
     }
+
     fn get_txout(
         &self,
         txid: &bitcoin::Txid,
@@ -806,14 +1299,26 @@ pub trait RpcApi: Sized {
             handle_defaults(&mut args, &defaults),
         )
     }
-    fn decoderawtransaction(&self) -> Result<()> {
-        unimplemented!()
+    fn decode_raw_transaction(&self, hex: &str) -> Result<DecodeRawTransactionResult> {
+        self.call("decoderawtransaction", &[into_json(hex)?])
     }
-    fn decodescript(&self) -> Result<()> {
-        unimplemented!()
+    fn decode_script(&self, hex: &str) -> Result<DecodeScriptResult> {
+        self.call("decodescript", &[into_json(hex)?])
     }
-    fn fundrawtransaction(&self) -> Result<()> {
-        unimplemented!()
+    /// Adds inputs from the wallet to the transaction until it covers its outputs and fee,
+    /// adding a change output if needed. Watch-only outputs are only used with
+    /// `include_watching`.
+    ///
+    /// The daemon picks the fee and the change address and position, and does not lock the
+    /// inputs; use `lock_unspent` on the inputs to keep them for this transaction.
+    fn fund_raw_transaction(
+        &self,
+        hex: &str,
+        include_watching: Option<bool>,
+    ) -> Result<FundRawTransactionResult> {
+        let mut args = [into_json(hex)?, opt_into_json(include_watching)?];
+
+        self.call("fundrawtransaction", handle_defaults(&mut args, &[null()]))
     }
     fn getrawtransaction(&self) -> Result<()> {
         unimplemented!()
@@ -822,8 +1327,32 @@ pub trait RpcApi: Sized {
         self.call("sendrawtransaction", &[signed_hex.into()])
     }
 
-    fn signrawtransaction(&self, hex: &str) -> Result<SignRawTransactionResult> {
-        self.call("signrawtransaction", &[hex.into()])
+    /// Signs the inputs of the transaction that the wallet or `privkeys` have keys for.
+    ///
+    /// `prevtxs` describes outputs the wallet doesn't know about, such as the inputs of a multisig
+    /// that is signed in several rounds. `branch_id` overrides the consensus branch id to sign for.
+    fn sign_raw_transaction(
+        &self,
+        hex: &str,
+        prevtxs: Option<&[SignRawTransactionInput]>,
+        privkeys: Option<&[PrivateKey]>,
+        sighash_type: Option<SigHashType>,
+        branch_id: Option<u32>,
+    ) -> Result<SignRawTransactionResult> {
+        let mut args = vec![
+            into_json(hex)?,
+            opt_into_json(prevtxs)?,
+            opt_into_json(privkeys)?,
+            opt_into_json(sighash_type)?,
+            opt_into_json(branch_id.map(|branch_id| format!("{:08x}", branch_id)))?,
+        ];
+        // The daemon skips null arguments, and an empty list of private keys would mean signing
+        // without the wallet's keys, so nulls are passed as is instead of through `handle_defaults`.
+        while args.last() == Some(&Value::Null) {
+            args.pop();
+        }
+
+        self.call("signrawtransaction", &args)
     }
 
     fn get_raw_transaction_verbose(
@@ -837,6 +1366,12 @@ pub trait RpcApi: Sized {
         self.call("getrawtransaction", &[into_json(txid)?, 0.into()])
     }
 
+    fn get_raw_transaction_decoded(&self, txid: &bitcoin::Txid) -> Result<raw::Transaction> {
+        let hex: String = self.call("getrawtransaction", &[into_json(txid)?, 0.into()])?;
+
+        Ok(raw::deserialize_hex(&hex)?)
+    }
+
     fn ping(&self) -> Result<()> {
         self.call("ping", &[])
     }
@@ -1141,10 +1676,33 @@ pub trait RpcApi: Sized {
 
 #[cfg(test)]
 mod tests {
-    use crate::client::{Auth, Client, ConfigFile};
+    use crate::client::{spent_info_error, Auth, Client, ConfigFile, Error};
 
     // todo https://github.com/iredelmeier/filesystem-rs/blob/master/src/lib.rs
 
+    #[test]
+    fn maps_spent_info_error() {
+        let txid = "1111111111111111111111111111111111111111111111111111111111111111"
+            .parse()
+            .unwrap();
+        let rpc_error = |code, message: &str| {
+            Error::JsonRPC(jsonrpc::Error::Rpc(jsonrpc::error::RpcError {
+                code,
+                message: message.to_string(),
+                data: None,
+            }))
+        };
+
+        assert!(matches!(
+            spent_info_error(rpc_error(-5, "Unable to get spent info"), txid, 1),
+            Error::SpentInfoUnavailable(t, 1) if t == txid
+        ));
+        assert!(matches!(
+            spent_info_error(rpc_error(-8, "Invalid txid"), txid, 1),
+            Error::JsonRPC(_)
+        ));
+    }
+
     #[test]
     fn get_config() {
         let config_file = ConfigFile::vrsc(false).unwrap();
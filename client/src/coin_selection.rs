@@ -0,0 +1,411 @@
+//! Choosing which unspent outputs of the wallet fund a transaction.
+//!
+//! Candidates come from `listunspent`. Outputs that are not spendable, locked with `lockunspent`
+//! or immature coinbase outputs are never selected. The selected outputs convert directly into
+//! the inputs of `createrawtransaction`.
+
+use crate::bitcoin::{OutPoint, Txid};
+use crate::client::{Result, RpcApi};
+use crate::json::vrsc::Amount;
+use crate::json::{CreateRawTransactionInput, ListUnspentResult};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The number of confirmations after which coinbase and staking outputs can be spent.
+pub const COINBASE_MATURITY: u32 = 100;
+
+// Estimated sizes of the parts of a transparent transaction, in bytes.
+const TX_OVERHEAD_SIZE: u64 = 30;
+pub(crate) const INPUT_SIZE: u64 = 148;
+const OUTPUT_SIZE: u64 = 34;
+
+const BNB_MAX_TRIES: usize = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Spends the largest outputs first, which keeps the number of inputs low.
+    LargestFirst,
+    /// Looks for a set of outputs that pays the target without change, and falls back to
+    /// largest-first if there is none.
+    BranchAndBound,
+    /// Spends the outputs with the most confirmations first, which leaves the outputs that are
+    /// still maturing for staking untouched.
+    OldestFirst,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fee {
+    /// A fixed fee for the transaction.
+    Fixed(Amount),
+    /// A fee per 1000 bytes of the estimated transaction size.
+    PerKb(Amount),
+}
+
+impl Fee {
+    /// The fee of a transaction with `inputs` inputs and `outputs` outputs.
+    pub fn for_size(&self, inputs: usize, outputs: usize) -> Amount {
+        match self {
+            Fee::Fixed(fee) => *fee,
+            Fee::PerKb(rate) => {
+                Amount::from_sat((rate.as_sat() * estimated_size(inputs, outputs)).div_ceil(1000))
+            }
+        }
+    }
+
+    fn per_input(&self) -> Amount {
+        self.for_size(1, 0) - self.for_size(0, 0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CoinSelectionError {
+    InsufficientFunds { available: Amount, needed: Amount },
+}
+
+impl fmt::Display for CoinSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinSelectionError::InsufficientFunds { available, needed } => write!(
+                f,
+                "insufficient funds: {} available, {} needed",
+                available, needed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CoinSelectionError {}
+
+#[derive(Clone, Debug)]
+pub struct CoinSelection {
+    pub selected: Vec<ListUnspentResult>,
+    pub total: Amount,
+    pub fee: Amount,
+    /// What is left after the target and the fee. Zero if no change output is needed.
+    pub change: Amount,
+}
+
+impl CoinSelection {
+    /// The selected outputs as inputs for `createrawtransaction`.
+    pub fn inputs(&self) -> Vec<CreateRawTransactionInput> {
+        self.selected
+            .iter()
+            .map(|utxo| CreateRawTransactionInput {
+                txid: utxo.txid,
+                vout: utxo.vout as u32,
+                sequence: None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CoinSelector {
+    utxos: Vec<ListUnspentResult>,
+    locked: HashSet<(Txid, u32)>,
+    min_confirmations: u32,
+    fee: Fee,
+    /// Change below this amount is added to the fee instead.
+    dust: Amount,
+}
+
+impl CoinSelector {
+    /// Selects from `utxos` with the default fee of 0.0001, at least one confirmation and no
+    /// locked outputs.
+    pub fn new(utxos: Vec<ListUnspentResult>) -> Self {
+        CoinSelector {
+            utxos,
+            locked: HashSet::new(),
+            min_confirmations: 1,
+            fee: Fee::Fixed(Amount::from_sat(10_000)),
+            dust: Amount::from_sat(546),
+        }
+    }
+
+    /// Creates a selector over the unspent outputs of the wallet, leaving out its locked outputs.
+    pub fn from_wallet<C: RpcApi>(client: &C) -> Result<Self> {
+        let utxos = client.list_unspent(None, None, None)?;
+        let locked = client.list_lock_unspent()?;
+
+        Ok(CoinSelector::new(utxos).locked(
+            locked
+                .iter()
+                .map(|locked| OutPoint::new(locked.txid, locked.vout as u32)),
+        ))
+    }
+
+    /// All unspent outputs the selector was created with, including those it leaves out.
+    pub fn utxos(&self) -> &[ListUnspentResult] {
+        &self.utxos
+    }
+
+    pub fn locked<I: IntoIterator<Item = OutPoint>>(mut self, outpoints: I) -> Self {
+        self.locked
+            .extend(outpoints.into_iter().map(|o| (o.txid, o.vout)));
+        self
+    }
+
+    pub fn min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    pub fn fee(mut self, fee: Fee) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn dust(mut self, dust: Amount) -> Self {
+        self.dust = dust;
+        self
+    }
+
+    /// The outputs that can be selected.
+    pub fn candidates(&self) -> impl Iterator<Item = &ListUnspentResult> {
+        self.utxos
+            .iter()
+            .filter(move |utxo| self.is_candidate(utxo))
+    }
+
+    fn is_candidate(&self, utxo: &ListUnspentResult) -> bool {
+        utxo.spendable
+            && !utxo.amount.is_negative()
+            && utxo.confirmations >= self.min_confirmations
+            && !(utxo.generated && utxo.confirmations < COINBASE_MATURITY)
+            && !self.locked.contains(&(utxo.txid, utxo.vout as u32))
+    }
+
+    /// Selects outputs that pay `target` to `outputs` outputs, plus the fee.
+    pub fn select(
+        &self,
+        target: Amount,
+        outputs: usize,
+        strategy: Strategy,
+    ) -> std::result::Result<CoinSelection, CoinSelectionError> {
+        let mut candidates: Vec<&ListUnspentResult> = self.candidates().collect();
+
+        match strategy {
+            Strategy::LargestFirst => candidates.sort_by_key(|utxo| Reverse(value(utxo))),
+            Strategy::OldestFirst => candidates.sort_by_key(|utxo| Reverse(utxo.confirmations)),
+            Strategy::BranchAndBound => {
+                candidates.sort_by_key(|utxo| Reverse(value(utxo)));
+                if let Some(selection) = self.branch_and_bound(&candidates, target, outputs) {
+                    return Ok(selection);
+                }
+            }
+        }
+
+        self.accumulate(&candidates, target, outputs)
+    }
+
+    /// Adds candidates in order until they cover the target and the fee.
+    fn accumulate(
+        &self,
+        candidates: &[&ListUnspentResult],
+        target: Amount,
+        outputs: usize,
+    ) -> std::result::Result<CoinSelection, CoinSelectionError> {
+        let mut total = Amount::ZERO;
+
+        for (count, utxo) in candidates.iter().enumerate() {
+            total += value(utxo);
+            let inputs = count + 1;
+
+            let fee = self.fee.for_size(inputs, outputs);
+            if total < target + fee {
+                continue;
+            }
+            // pay for a change output only if there is enough left for one
+            let fee_with_change = self.fee.for_size(inputs, outputs + 1);
+            let change = match total.checked_sub(target + fee_with_change) {
+                Some(change) if change >= self.dust => change,
+                _ => Amount::ZERO,
+            };
+            let fee = match change {
+                Amount::ZERO => total - target,
+                _ => fee_with_change,
+            };
+
+            return Ok(selection(&candidates[..inputs], total, fee, change));
+        }
+
+        Err(CoinSelectionError::InsufficientFunds {
+            available: total,
+            needed: target + self.fee.for_size(candidates.len().max(1), outputs),
+        })
+    }
+
+    /// Searches for a set of candidates, sorted from large to small, whose value after the fee
+    /// for spending them pays the target without leaving enough for a change output.
+    fn branch_and_bound(
+        &self,
+        candidates: &[&ListUnspentResult],
+        target: Amount,
+        outputs: usize,
+    ) -> Option<CoinSelection> {
+        let per_input = self.fee.per_input().as_sat();
+        let effective: Vec<u64> = candidates
+            .iter()
+            .map(|utxo| value(utxo).as_sat().saturating_sub(per_input))
+            .collect();
+
+        let target = (target + self.fee.for_size(0, outputs)).as_sat();
+        let cost_of_change = (self.dust + self.fee.for_size(0, 1)).as_sat();
+        let upper = target + cost_of_change;
+
+        let mut remaining: u64 = effective.iter().sum();
+        if remaining < target {
+            return None;
+        }
+
+        let mut selected: Vec<bool> = vec![];
+        let mut current = 0u64;
+        let mut best: Option<(u64, Vec<bool>)> = None;
+
+        for _ in 0..BNB_MAX_TRIES {
+            let backtrack = if current > upper || current + remaining < target {
+                true
+            } else if current >= target {
+                let waste = current - target;
+                if !matches!(&best, Some((best_waste, _)) if *best_waste <= waste) {
+                    best = Some((waste, selected.clone()));
+                    if waste == 0 {
+                        break;
+                    }
+                }
+                true
+            } else {
+                false
+            };
+
+            if backtrack {
+                // undo the last inclusion and try leaving that candidate out instead
+                while let Some(false) = selected.last() {
+                    selected.pop();
+                    remaining += effective[selected.len()];
+                }
+                match selected.last_mut() {
+                    Some(last) => {
+                        *last = false;
+                        current -= effective[selected.len() - 1];
+                    }
+                    None => break,
+                }
+            } else {
+                let index = selected.len();
+                if index == effective.len() {
+                    continue;
+                }
+                remaining -= effective[index];
+                current += effective[index];
+                selected.push(true);
+            }
+        }
+
+        let (_, best) = best?;
+        let chosen: Vec<&ListUnspentResult> = candidates
+            .iter()
+            .zip(best.iter())
+            .filter(|(_, selected)| **selected)
+            .map(|(utxo, _)| *utxo)
+            .collect();
+        let total = chosen
+            .iter()
+            .fold(Amount::ZERO, |acc, utxo| acc + value(utxo));
+        let target = Amount::from_sat(target) - self.fee.for_size(0, outputs);
+
+        Some(selection(&chosen, total, total - target, Amount::ZERO))
+    }
+}
+
+/// The estimated size in bytes of a transparent transaction.
+pub fn estimated_size(inputs: usize, outputs: usize) -> u64 {
+    TX_OVERHEAD_SIZE + inputs as u64 * INPUT_SIZE + outputs as u64 * OUTPUT_SIZE
+}
+
+pub(crate) fn value(utxo: &ListUnspentResult) -> Amount {
+    Amount::from_sat(utxo.amount.as_sat().max(0) as u64)
+}
+
+fn selection(
+    utxos: &[&ListUnspentResult],
+    total: Amount,
+    fee: Amount,
+    change: Amount,
+) -> CoinSelection {
+    CoinSelection {
+        selected: utxos.iter().map(|utxo| (*utxo).clone()).collect(),
+        total,
+        fee,
+        change,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const TXID: &str =
+        "1111111111111111111111111111111111111111111111111111111111111111";
+
+    /// Output `n` of `TXID`, paying to a single address.
+    pub(crate) fn utxo(
+        n: u16,
+        vrsc: f64,
+        confirmations: u32,
+        generated: bool,
+    ) -> ListUnspentResult {
+        serde_json::from_value(serde_json::json!({
+            "txid": TXID,
+            "vout": n,
+            "generated": generated,
+            "address": "R9HC5WtHbpoa51NCUAz86XLCmGTbkf45NT",
+            "scriptPubKey": "76a914000000000000000000000000000000000000000088ac",
+            "amount": vrsc,
+            "confirmations": confirmations,
+            "spendable": true
+        }))
+        .unwrap()
+    }
+
+    fn vouts(selection: &CoinSelection) -> Vec<u32> {
+        selection.inputs().iter().map(|input| input.vout).collect()
+    }
+
+    #[test]
+    fn selects_by_strategy() {
+        let utxos = vec![
+            utxo(0, 5.0, 10, false),
+            utxo(1, 3.0, 500, false),
+            utxo(2, 2.0001, 20, false),
+            utxo(3, 50.0, 50, true),
+            utxo(4, 40.0, 30, false),
+            utxo(5, 1.0, 1000, false),
+        ];
+        let selector =
+            CoinSelector::new(utxos).locked(vec![OutPoint::new(TXID.parse().unwrap(), 4)]);
+        let target = Amount::from_vrsc(2.0).unwrap();
+
+        // the immature coinbase and the locked output are never selected
+        let largest = selector.select(target, 1, Strategy::LargestFirst).unwrap();
+        assert_eq!(vouts(&largest), vec![0]);
+        assert_eq!(largest.fee, Amount::from_sat(10_000));
+        assert_eq!(largest.change, Amount::from_vrsc(2.9999).unwrap());
+
+        let oldest = selector.select(target, 1, Strategy::OldestFirst).unwrap();
+        assert_eq!(vouts(&oldest), vec![5, 1]);
+
+        // pays the target and fee exactly, without change
+        let exact = selector
+            .select(target, 1, Strategy::BranchAndBound)
+            .unwrap();
+        assert_eq!(vouts(&exact), vec![2]);
+        assert_eq!(exact.change, Amount::ZERO);
+
+        assert!(matches!(
+            selector.select(Amount::from_vrsc(20.0).unwrap(), 1, Strategy::LargestFirst),
+            Err(CoinSelectionError::InsufficientFunds { .. })
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//! Merging many small unspent outputs per address into one.
+//!
+//! Staking and mining wallets collect many small outputs, which make transactions large and
+//! expensive. The planner groups the outputs of the wallet by address and plans one transaction
+//! per batch that fits in the size limit, paying back to the same address. Planning sends
+//! nothing, so it doubles as a dry run that reports the fees and the resulting number of outputs.
+
+use crate::bitcoin::{OutPoint, Txid};
+use crate::client::{Result, RpcApi};
+use crate::coin_selection::{estimated_size, value, CoinSelector, Fee, INPUT_SIZE};
+use crate::error::Error;
+use crate::json::vrsc::{Address, Amount};
+use crate::json::{CreateRawTransactionInput, ListUnspentResult};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+pub struct ConsolidationBatch {
+    pub address: Address,
+    pub utxos: Vec<ListUnspentResult>,
+    pub total: Amount,
+    pub fee: Amount,
+    /// The estimated size of the transaction, in bytes.
+    pub size: u64,
+}
+
+impl ConsolidationBatch {
+    /// What the consolidated output receives.
+    pub fn output(&self) -> Amount {
+        self.total - self.fee
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsolidationPlan {
+    pub batches: Vec<ConsolidationBatch>,
+    /// The number of unspent outputs before consolidating.
+    pub utxos_before: usize,
+    /// The number of unspent outputs after all batches are sent.
+    pub utxos_after: usize,
+}
+
+impl ConsolidationPlan {
+    pub fn total_fee(&self) -> Amount {
+        self.batches
+            .iter()
+            .fold(Amount::ZERO, |acc, batch| acc + batch.fee)
+    }
+
+    /// Creates, signs and sends a transaction per batch, and returns their txids in order.
+    ///
+    /// Stops at the first batch that fails; the batches before it are sent already.
+    pub fn execute<C: RpcApi>(&self, client: &C) -> Result<Vec<Txid>> {
+        let mut txids = vec![];
+
+        for batch in self.batches.iter() {
+            let inputs: Vec<_> = batch
+                .utxos
+                .iter()
+                .map(|utxo| CreateRawTransactionInput {
+                    txid: utxo.txid,
+                    vout: utxo.vout as u32,
+                    sequence: None,
+                })
+                .collect();
+            let mut outputs = HashMap::new();
+            outputs.insert(batch.address.to_string(), batch.output());
+
+            let hex = client.createrawtransaction(&inputs, &outputs, None, None)?;
+            let signed = client.sign_raw_transaction(&hex, None, None, None, None)?;
+            if !signed.complete {
+                return Err(Error::VRSCError(format!(
+                    "consolidation transaction for {} could not be fully signed",
+                    batch.address
+                )));
+            }
+            let txid = client.sendrawtransaction(&signed.hex)?;
+
+            txids.push(Txid::from_str(&txid).map_err(|e| Error::VRSCError(e.to_string()))?);
+        }
+
+        Ok(txids)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsolidationPlanner {
+    selector: CoinSelector,
+    max_size: u64,
+    fee: Fee,
+    min_inputs: usize,
+    below: Option<Amount>,
+}
+
+impl ConsolidationPlanner {
+    /// Plans over `utxos` with transactions of at most 100 kB, the default fee of 0.0001 per
+    /// transaction, and batches of at least 10 outputs.
+    ///
+    /// Locked outputs are only left out when they are passed to `locked`, or when the planner is
+    /// created with `from_wallet`.
+    pub fn new(utxos: Vec<ListUnspentResult>) -> Self {
+        ConsolidationPlanner::with_selector(CoinSelector::new(utxos))
+    }
+
+    /// Plans over the unspent outputs of the wallet, leaving out its locked outputs.
+    pub fn from_wallet<C: RpcApi>(client: &C) -> Result<Self> {
+        CoinSelector::from_wallet(client).map(ConsolidationPlanner::with_selector)
+    }
+
+    fn with_selector(selector: CoinSelector) -> Self {
+        ConsolidationPlanner {
+            selector,
+            max_size: 100_000,
+            fee: Fee::Fixed(Amount::from_sat(10_000)),
+            min_inputs: 10,
+            below: None,
+        }
+    }
+
+    pub fn locked<I: IntoIterator<Item = OutPoint>>(mut self, outpoints: I) -> Self {
+        self.selector = self.selector.locked(outpoints);
+        self
+    }
+
+    /// The maximum estimated size of each transaction, in bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn fee(mut self, fee: Fee) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// The smallest number of outputs worth consolidating in one transaction.
+    pub fn min_inputs(mut self, min_inputs: usize) -> Self {
+        self.min_inputs = min_inputs.max(2);
+        self
+    }
+
+    /// Only consolidates outputs smaller than `amount`, to sweep dust.
+    pub fn below(mut self, amount: Amount) -> Self {
+        self.below = Some(amount);
+        self
+    }
+
+    /// Plans the consolidation without sending anything.
+    pub fn plan(&self) -> ConsolidationPlan {
+        let mut by_address: BTreeMap<&Address, Vec<&ListUnspentResult>> = BTreeMap::new();
+        for utxo in self.selector.candidates() {
+            if matches!(self.below, Some(below) if value(utxo) >= below) {
+                continue;
+            }
+            if let Some(address) = &utxo.address {
+                by_address.entry(address).or_default().push(utxo);
+            }
+        }
+
+        let max_inputs = self.max_size.saturating_sub(estimated_size(0, 1)) / INPUT_SIZE;
+        let max_inputs = usize::try_from(max_inputs).unwrap_or(usize::MAX).max(1);
+
+        let mut batches = vec![];
+        for (address, mut utxos) in by_address {
+            // spend the smallest outputs first
+            utxos.sort_by_key(|utxo| value(utxo));
+
+            for chunk in utxos.chunks(max_inputs) {
+                if chunk.len() < self.min_inputs {
+                    continue;
+                }
+                let total = chunk
+                    .iter()
+                    .fold(Amount::ZERO, |acc, utxo| acc + value(utxo));
+                let fee = self.fee.for_size(chunk.len(), 1);
+                if total <= fee {
+                    continue;
+                }
+
+                batches.push(ConsolidationBatch {
+                    address: address.clone(),
+                    utxos: chunk.iter().map(|utxo| (*utxo).clone()).collect(),
+                    total,
+                    fee,
+                    size: estimated_size(chunk.len(), 1),
+                });
+            }
+        }
+
+        let consolidated: usize = batches.iter().map(|batch| batch.utxos.len()).sum();
+
+        let utxo_count = self.selector.utxos().len();
+
+        ConsolidationPlan {
+            utxos_before: utxo_count,
+            utxos_after: utxo_count - consolidated + batches.len(),
+            batches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coin_selection::tests::{utxo, TXID};
+
+    #[test]
+    fn plans_batches_without_locked_or_immature_outputs() {
+        let mut utxos: Vec<ListUnspentResult> = (0..25).map(|n| utxo(n, 0.1, 200, true)).collect();
+        utxos.push(utxo(25, 0.1, 50, true));
+        utxos.push(utxo(26, 100.0, 200, false));
+
+        let plan = ConsolidationPlanner::new(utxos)
+            .locked(vec![OutPoint::new(TXID.parse().unwrap(), 0)])
+            .below(Amount::ONE_VRSC)
+            .min_inputs(2)
+            .max_size(estimated_size(10, 1))
+            .plan();
+
+        // 24 mature outputs in batches of 10, 10 and 4
+        assert_eq!(plan.batches.len(), 3);
+        assert_eq!(plan.batches[2].utxos.len(), 4);
+        assert!(plan
+            .batches
+            .iter()
+            .all(|batch| batch.size <= estimated_size(10, 1)));
+        assert_eq!(plan.batches[0].output(), Amount::from_vrsc(0.9999).unwrap());
+        assert_eq!(plan.total_fee(), Amount::from_vrsc(0.0003).unwrap());
+        assert_eq!(plan.utxos_before, 27);
+        assert_eq!(plan.utxos_after, 6);
+
+        let unlimited = ConsolidationPlanner::new(vec![utxo(0, 0.1, 200, false); 12])
+            .max_size(u64::MAX)
+            .plan();
+        assert_eq!(unlimited.batches.len(), 1);
+    }
+}
@@ -0,0 +1,93 @@
+//! Paging over `getcurrencystate` for block ranges too large to request in one call.
+
+use crate::client::{Result, RpcApi};
+use crate::json::currency::CurrencyStateRange;
+use crate::json::{GetCurrencyStateResult, GetCurrencyStateResultInner};
+use std::collections::VecDeque;
+
+/// Yields the state of a currency every `step` blocks of a range, requesting at most `page_size`
+/// data points per call. Pages do not overlap, and the total volume entry of each page is left out.
+pub struct CurrencyStatePages<'a, C: RpcApi> {
+    client: &'a C,
+    currency: String,
+    express_in: Option<String>,
+    next: u64,
+    end: u64,
+    step: u64,
+    page_size: u64,
+    buffer: VecDeque<GetCurrencyStateResultInner>,
+    done: bool,
+}
+
+impl<'a, C: RpcApi> CurrencyStatePages<'a, C> {
+    /// Pages over `range` 100 data points at a time. A range without a step is taken every block.
+    pub fn new(client: &'a C, currency: &str, range: CurrencyStateRange) -> Self {
+        let step = range.step.unwrap_or(1).max(1);
+
+        CurrencyStatePages {
+            client,
+            currency: currency.to_string(),
+            express_in: None,
+            next: range.start,
+            end: range.end,
+            step,
+            page_size: 100,
+            buffer: VecDeque::new(),
+            done: range.start > range.end,
+        }
+    }
+
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Expresses the conversion data in this currency instead of the queried one.
+    pub fn express_in(mut self, currency: &str) -> Self {
+        self.express_in = Some(currency.to_string());
+        self
+    }
+
+    fn fetch_page(&mut self) -> Result<()> {
+        let span = self.step.saturating_mul(self.page_size - 1);
+        let page_end = self.next.saturating_add(span).min(self.end);
+        let range = CurrencyStateRange::new(self.next, page_end).step(self.step);
+
+        let results = self.client.get_currency_state(
+            &self.currency,
+            Some(&range),
+            self.express_in.as_deref(),
+        )?;
+        self.buffer.extend(
+            results
+                .into_iter()
+                .filter_map(|result| match result {
+                    GetCurrencyStateResult::Data(data) => Some(data),
+                    GetCurrencyStateResult::TotalVolume { .. } => None,
+                })
+                .filter(|data| data.height >= self.next && data.height <= page_end),
+        );
+
+        match page_end.checked_add(self.step) {
+            Some(next) if page_end < self.end => self.next = next,
+            _ => self.done = true,
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, C: RpcApi> Iterator for CurrencyStatePages<'a, C> {
+    type Item = Result<GetCurrencyStateResultInner>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buffer.is_empty() && !self.done {
+            if let Err(e) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+
+        self.buffer.pop_front().map(Ok)
+    }
+}
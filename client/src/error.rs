@@ -1,5 +1,9 @@
+use crate::operation::OperationError;
 use std::num::ParseIntError;
 use std::{error, fmt, fmt::Formatter, io};
+use vrsc_rpc_json::bitcoin::consensus::encode;
+use vrsc_rpc_json::bitcoin::Txid;
+use vrsc_rpc_json::currency::CurrencyDefinitionError;
 use vrsc_rpc_json::vrsc;
 
 #[derive(Debug)]
@@ -11,6 +15,12 @@ pub enum Error {
     Json(serde_json::error::Error),
     VRSCError(String),
     InvalidAmount(vrsc::util::amount::ParseAmountError),
+    Operation(OperationError),
+    InvalidCurrencyDefinition(CurrencyDefinitionError),
+    NotAReserveTransfer(Txid, u32),
+    ConsensusDecode(encode::Error),
+    /// No spending transaction was found for the output, or the daemon has no spent index.
+    SpentInfoUnavailable(Txid, u32),
 }
 
 impl error::Error for Error {
@@ -23,6 +33,11 @@ impl error::Error for Error {
             Error::Json(ref e) => Some(e),
             Error::VRSCError(_) => None,
             Error::InvalidAmount(ref e) => Some(e),
+            Error::Operation(ref e) => Some(e),
+            Error::InvalidCurrencyDefinition(ref e) => Some(e),
+            Error::NotAReserveTransfer(..) => None,
+            Error::ConsensusDecode(ref e) => Some(e),
+            Error::SpentInfoUnavailable(..) => None,
         }
     }
 }
@@ -37,6 +52,19 @@ impl fmt::Display for Error {
             Error::Json(ref e) => write!(f, "JSON error: {}", e),
             Error::VRSCError(ref e) => write!(f, "VRSC daemon error: {}", e),
             Error::InvalidAmount(ref e) => write!(f, "invalid amount: {}", e),
+            Error::Operation(ref e) => write!(f, "operation error: {}", e),
+            Error::InvalidCurrencyDefinition(ref e) => {
+                write!(f, "invalid currency definition: {}", e)
+            }
+            Error::NotAReserveTransfer(ref txid, vout) => {
+                write!(f, "output {}:{} is not a reserve transfer", txid, vout)
+            }
+            Error::ConsensusDecode(ref e) => write!(f, "consensus decode error: {}", e),
+            Error::SpentInfoUnavailable(ref txid, index) => write!(
+                f,
+                "no spent info for {}:{}, it is unspent or the daemon runs without -spentindex",
+                txid, index
+            ),
         }
     }
 }
@@ -70,3 +98,15 @@ impl From<vrsc::util::amount::ParseAmountError> for Error {
         Error::InvalidAmount(e)
     }
 }
+
+impl From<CurrencyDefinitionError> for Error {
+    fn from(e: CurrencyDefinitionError) -> Error {
+        Error::InvalidCurrencyDefinition(e)
+    }
+}
+
+impl From<encode::Error> for Error {
+    fn from(e: encode::Error) -> Error {
+        Error::ConsensusDecode(e)
+    }
+}
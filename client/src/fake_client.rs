@@ -0,0 +1,40 @@
+//! An `RpcApi` that answers from canned responses, for testing the helpers built on top of it.
+
+use crate::client::{Result, RpcApi};
+use serde_json::Value;
+use std::cell::RefCell;
+
+pub(crate) struct FakeClient<F> {
+    respond: F,
+    calls: RefCell<Vec<(String, Vec<Value>)>>,
+}
+
+impl<F: Fn(&str, &[Value]) -> Value> FakeClient<F> {
+    /// Answers every call with what `respond` returns for its method and arguments.
+    pub(crate) fn new(respond: F) -> Self {
+        FakeClient {
+            respond,
+            calls: RefCell::new(vec![]),
+        }
+    }
+
+    /// The arguments of every call to `method`, in order.
+    pub(crate) fn calls(&self, method: &str) -> Vec<Vec<Value>> {
+        self.calls
+            .borrow()
+            .iter()
+            .filter(|(cmd, _)| cmd == method)
+            .map(|(_, args)| args.clone())
+            .collect()
+    }
+}
+
+impl<F: Fn(&str, &[Value]) -> Value> RpcApi for FakeClient<F> {
+    fn call<T: for<'a> serde::de::Deserialize<'a>>(&self, cmd: &str, args: &[Value]) -> Result<T> {
+        self.calls
+            .borrow_mut()
+            .push((cmd.to_string(), args.to_vec()));
+
+        Ok(serde_json::from_value((self.respond)(cmd, args))?)
+    }
+}
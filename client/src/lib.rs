@@ -8,7 +8,16 @@ extern crate serde_json;
 
 mod chain_config;
 pub mod client;
+pub mod coin_selection;
+pub mod consolidation;
+pub mod currency_state;
 mod error;
+#[cfg(test)]
+mod fake_client;
+pub mod multisig;
+pub mod operation;
+pub mod orderbook;
+pub mod transfer;
 
 pub extern crate jsonrpc;
 pub extern crate vrsc_rpc_json;
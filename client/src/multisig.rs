@@ -0,0 +1,473 @@
+//! Collecting signatures for a transaction from several wallets.
+//!
+//! A `PartiallySignedTransaction` carries the transaction as created by `createrawtransaction`,
+//! together with the outputs it spends and their redeem scripts, so that any wallet holding one
+//! of the keys can sign it without knowing about those outputs. It is passed around as a JSON
+//! file. Signatures made by different wallets are combined by the daemon: `signrawtransaction`
+//! merges the signatures of all transactions that are concatenated in its hex argument.
+//!
+//! The daemon orders the signatures of a multisig input by key, but leaves no gaps for keys that
+//! have not signed, so which keys signed is found by verifying each signature against the keys.
+
+use crate::bitcoin::blockdata::opcodes;
+use crate::bitcoin::blockdata::script::Instruction;
+use crate::bitcoin::secp256k1::{self, ecdsa, Message, Secp256k1};
+use crate::bitcoin::{Script, Txid};
+use crate::client::{Result, RpcApi};
+use crate::json::raw::{self, Transaction};
+use crate::json::vrsc::{Amount, PrivateKey, PublicKey};
+use crate::json::{CreateRawTransactionInput, SignRawTransactionInput};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// An output spent by the transaction, with what is needed to sign for it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PartialInput {
+    pub txid: Txid,
+    pub vout: u32,
+    pub script_pub_key: Script,
+    /// The multisig script of a P2SH output.
+    pub redeem_script: Option<Script>,
+    #[serde(with = "crate::json::vrsc::util::amount::serde::as_vrsc::opt", default)]
+    pub amount: Option<Amount>,
+    /// The keys whose signatures are in the input, found by verifying them. Only known for
+    /// multisig inputs.
+    #[serde(default)]
+    pub signed_by: Vec<PublicKey>,
+}
+
+impl PartialInput {
+    pub fn new(txid: Txid, vout: u32, script_pub_key: Script) -> Self {
+        PartialInput {
+            txid,
+            vout,
+            script_pub_key,
+            redeem_script: None,
+            amount: None,
+            signed_by: vec![],
+        }
+    }
+
+    pub fn redeem_script(mut self, redeem_script: Script) -> Self {
+        self.redeem_script = Some(redeem_script);
+        self
+    }
+
+    pub fn amount(mut self, amount: Amount) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// The number of signatures required and the keys that can sign, if the redeem script is a
+    /// multisig script.
+    pub fn multisig_keys(&self) -> Option<(usize, Vec<PublicKey>)> {
+        parse_multisig(self.redeem_script.as_ref()?)
+    }
+
+    fn prevtx(&self) -> SignRawTransactionInput {
+        SignRawTransactionInput {
+            txid: self.txid,
+            vout: self.vout,
+            script_pub_key: self.script_pub_key.clone(),
+            redeem_script: self.redeem_script.clone(),
+            amount: self.amount,
+        }
+    }
+}
+
+/// The signatures an input still needs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingSignatures {
+    pub input: usize,
+    pub txid: Txid,
+    pub vout: u32,
+    /// How many more signatures are needed.
+    pub needed: usize,
+    /// The keys that have not signed yet, any `needed` of which complete the input. Empty if the
+    /// input is not a multisig, as its key is not known from the output it spends.
+    pub keys: Vec<PublicKey>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PartiallySignedTransaction {
+    /// The transaction as it was created, without signatures.
+    pub unsigned_hex: String,
+    /// The transaction with the signatures collected so far.
+    pub hex: String,
+    pub inputs: Vec<PartialInput>,
+    pub complete: bool,
+}
+
+impl PartiallySignedTransaction {
+    /// Creates the unsigned transaction spending `inputs`, in order.
+    pub fn create<C: RpcApi>(
+        client: &C,
+        inputs: Vec<PartialInput>,
+        outputs: &HashMap<String, Amount>,
+        locktime: Option<i64>,
+        expiryheight: Option<u64>,
+    ) -> Result<Self> {
+        let raw_inputs: Vec<CreateRawTransactionInput> = inputs
+            .iter()
+            .map(|input| CreateRawTransactionInput {
+                txid: input.txid,
+                vout: input.vout,
+                sequence: None,
+            })
+            .collect();
+        let hex = client.createrawtransaction(&raw_inputs, outputs, locktime, expiryheight)?;
+
+        Ok(PartiallySignedTransaction {
+            unsigned_hex: hex.clone(),
+            hex,
+            inputs,
+            complete: false,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+
+    /// Adds the signatures that the wallet of `client`, or `privkeys` if given, can make.
+    pub fn sign<C: RpcApi>(&mut self, client: &C, privkeys: Option<&[PrivateKey]>) -> Result<bool> {
+        self.apply(client, &self.hex.clone(), privkeys)?;
+
+        Ok(self.complete)
+    }
+
+    /// Combines the signatures collected in `other`, for the same transaction, into this one.
+    /// No keys of the wallet of `client` are used.
+    pub fn merge<C: RpcApi>(
+        &mut self,
+        client: &C,
+        other: &PartiallySignedTransaction,
+    ) -> Result<bool> {
+        if other.unsigned_hex != self.unsigned_hex {
+            return Err(crate::Error::VRSCError(String::from(
+                "cannot merge signatures of a different transaction",
+            )));
+        }
+
+        let combined = format!("{}{}", self.hex, other.hex);
+        self.apply(client, &combined, Some(&[]))?;
+
+        Ok(self.complete)
+    }
+
+    /// The inputs that need more signatures, and the keys that can still sign them. Inputs that
+    /// don't spend a multisig are reported while they have no signature at all.
+    pub fn missing_signatures(&self) -> Vec<MissingSignatures> {
+        if self.complete {
+            return vec![];
+        }
+
+        let tx: Option<Transaction> = raw::deserialize_hex(&self.hex).ok();
+
+        self.inputs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, input)| {
+                let (needed, keys) = match input.multisig_keys() {
+                    Some((required, keys)) => {
+                        let signed = keys
+                            .iter()
+                            .filter(|key| input.signed_by.contains(key))
+                            .count();
+                        let keys = keys
+                            .into_iter()
+                            .filter(|key| !input.signed_by.contains(key))
+                            .collect();
+                        (required.saturating_sub(signed), keys)
+                    }
+                    None => {
+                        let signed = tx
+                            .as_ref()
+                            .and_then(|tx| tx.input.get(index))
+                            .is_some_and(|txin| !txin.script_sig.is_empty());
+                        (usize::from(!signed), vec![])
+                    }
+                };
+                if needed == 0 {
+                    return None;
+                }
+
+                Some(MissingSignatures {
+                    input: index,
+                    txid: input.txid,
+                    vout: input.vout,
+                    needed,
+                    keys,
+                })
+            })
+            .collect()
+    }
+
+    fn apply<C: RpcApi>(
+        &mut self,
+        client: &C,
+        hex: &str,
+        privkeys: Option<&[PrivateKey]>,
+    ) -> Result<()> {
+        // multisig inputs need their amount to verify who signed them
+        for input in self.inputs.iter_mut() {
+            if input.amount.is_none() && input.multisig_keys().is_some() {
+                let txout = client.get_txout(&input.txid, input.vout, Some(true))?;
+                input.amount = Some(Amount::from_vrsc(txout.value)?);
+            }
+        }
+
+        let prevtxs: Vec<SignRawTransactionInput> =
+            self.inputs.iter().map(PartialInput::prevtx).collect();
+        let signed = client.sign_raw_transaction(hex, Some(&prevtxs), privkeys, None, None)?;
+
+        self.hex = signed.hex;
+        self.complete = signed.complete;
+
+        let consensus_branch_id =
+            u32::from_str_radix(&client.get_blockchain_info()?.consensus.chaintip, 16)?;
+        let tx: Transaction = raw::deserialize_hex(&self.hex)?;
+        for (index, input) in self.inputs.iter_mut().enumerate() {
+            input.signed_by = signers(&tx, index, input, consensus_branch_id);
+        }
+
+        Ok(())
+    }
+}
+
+/// The keys of a multisig input whose signatures in `tx` verify, in the order of the redeem
+/// script.
+fn signers(
+    tx: &Transaction,
+    index: usize,
+    input: &PartialInput,
+    consensus_branch_id: u32,
+) -> Vec<PublicKey> {
+    let (keys, redeem_script, amount) =
+        match (input.multisig_keys(), &input.redeem_script, input.amount) {
+            (Some((_, keys)), Some(redeem_script), Some(amount)) => (keys, redeem_script, amount),
+            _ => return vec![],
+        };
+    let script_sig = match tx.input.get(index) {
+        Some(txin) => &txin.script_sig,
+        None => return vec![],
+    };
+
+    let secp = Secp256k1::verification_only();
+    let mut signers = vec![];
+    for instruction in script_sig.instructions() {
+        // the signatures, between the leading OP_0 and the redeem script
+        let (sighash_type, der) = match instruction {
+            Ok(Instruction::PushBytes(bytes)) if bytes != redeem_script.as_bytes() => {
+                match bytes.split_last() {
+                    Some((sighash_type, der)) => (*sighash_type as u32, der),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        let message = match tx
+            .signature_hash(
+                index,
+                redeem_script,
+                amount.as_sat(),
+                sighash_type,
+                consensus_branch_id,
+            )
+            .map(|sighash| Message::from_slice(&sighash))
+        {
+            Some(Ok(message)) => message,
+            _ => continue,
+        };
+        let mut signature = match ecdsa::Signature::from_der_lax(der) {
+            Ok(signature) => signature,
+            Err(_) => continue,
+        };
+        signature.normalize_s();
+
+        let signer = keys.iter().find(|key| {
+            !signers.contains(*key)
+                && secp256k1::PublicKey::from_slice(&key.to_bytes())
+                    .is_ok_and(|key| secp.verify_ecdsa(&message, &signature, &key).is_ok())
+        });
+        if let Some(signer) = signer {
+            signers.push(*signer);
+        }
+    }
+
+    keys.into_iter()
+        .filter(|key| signers.contains(key))
+        .collect()
+}
+
+/// Parses `OP_m <pubkey>... OP_n OP_CHECKMULTISIG`.
+fn parse_multisig(script: &Script) -> Option<(usize, Vec<PublicKey>)> {
+    let mut instructions = script.instructions();
+
+    let required = match instructions.next()?.ok()? {
+        Instruction::Op(op) => small_int(op)?,
+        _ => return None,
+    };
+
+    let mut keys = vec![];
+    loop {
+        match instructions.next()?.ok()? {
+            Instruction::PushBytes(bytes) => keys.push(PublicKey::from_slice(bytes).ok()?),
+            Instruction::Op(op) => {
+                if small_int(op)? != keys.len() {
+                    return None;
+                }
+                break;
+            }
+        }
+    }
+
+    match instructions.next()?.ok()? {
+        Instruction::Op(opcodes::all::OP_CHECKMULTISIG) if instructions.next().is_none() => {
+            Some((required, keys))
+        }
+        _ => None,
+    }
+}
+
+fn small_int(op: opcodes::All) -> Option<usize> {
+    let code = op.to_u8();
+    let first = opcodes::all::OP_PUSHNUM_1.to_u8();
+    let last = opcodes::all::OP_PUSHNUM_16.to_u8();
+
+    (first..=last)
+        .contains(&code)
+        .then(|| (code - first + 1) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const KEYS: [&str; 3] = [
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+    ];
+
+    fn two_of_three() -> Script {
+        let mut builder = crate::bitcoin::blockdata::script::Builder::new()
+            .push_opcode(opcodes::all::OP_PUSHNUM_2);
+        for key in KEYS {
+            builder = builder.push_slice(&PublicKey::from_str(key).unwrap().to_bytes());
+        }
+        builder
+            .push_opcode(opcodes::all::OP_PUSHNUM_3)
+            .push_opcode(opcodes::all::OP_CHECKMULTISIG)
+            .into_script()
+    }
+
+    const BRANCH_ID: u32 = 0x76b8_09bb;
+
+    // A Sapling transaction spending a 2 of 3 multisig output and a single key output, in which
+    // only the third key signed the multisig input. The keys are those of secret keys 1, 2 and 3.
+    fn partially_signed(txid: Txid, amount: Amount) -> Transaction {
+        let mut tx = Transaction {
+            overwintered: true,
+            version: 4,
+            version_group_id: raw::SAPLING_VERSION_GROUP_ID,
+            input: (0..2)
+                .map(|vout| crate::bitcoin::TxIn {
+                    previous_output: crate::bitcoin::OutPoint::new(txid, vout),
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![crate::bitcoin::TxOut {
+                value: 99_990_000,
+                script_pubkey: Script::new(),
+            }],
+            lock_time: 0,
+            expiry_height: 1000,
+            value_balance: 0,
+            shielded_spends: vec![],
+            shielded_outputs: vec![],
+            join_splits: vec![],
+            join_split_pubkey: None,
+            join_split_sig: None,
+            binding_sig: None,
+        };
+
+        let sighash = tx
+            .signature_hash(
+                0,
+                &two_of_three(),
+                amount.as_sat(),
+                raw::SIGHASH_ALL,
+                BRANCH_ID,
+            )
+            .unwrap();
+        let mut secret = [0; 32];
+        secret[31] = 3;
+        let signature = Secp256k1::signing_only().sign_ecdsa(
+            &Message::from_slice(&sighash).unwrap(),
+            &secp256k1::SecretKey::from_slice(&secret).unwrap(),
+        );
+        let mut signature = signature.serialize_der().to_vec();
+        signature.push(raw::SIGHASH_ALL as u8);
+
+        // as the daemon combines them: signatures first, then OP_0 for the missing ones
+        tx.input[0].script_sig = crate::bitcoin::blockdata::script::Builder::new()
+            .push_opcode(opcodes::all::OP_PUSHBYTES_0)
+            .push_slice(&signature)
+            .push_opcode(opcodes::all::OP_PUSHBYTES_0)
+            .push_slice(two_of_three().as_bytes())
+            .into_script();
+
+        tx
+    }
+
+    #[test]
+    fn reports_missing_signatures() {
+        let keys: Vec<PublicKey> = KEYS
+            .iter()
+            .map(|key| PublicKey::from_str(key).unwrap())
+            .collect();
+        let txid =
+            Txid::from_str("1111111111111111111111111111111111111111111111111111111111111111")
+                .unwrap();
+        let amount = Amount::ONE_VRSC;
+        let input = PartialInput::new(txid, 0, Script::new())
+            .redeem_script(two_of_three())
+            .amount(amount);
+        assert_eq!(input.multisig_keys(), Some((2, keys.clone())));
+
+        // the signature is in the first position, but it is the third key's
+        let tx = partially_signed(txid, amount);
+        assert_eq!(signers(&tx, 0, &input, BRANCH_ID), vec![keys[2]]);
+        assert!(signers(&tx, 0, &input, BRANCH_ID + 1).is_empty());
+        assert!(signers(&tx, 0, &input.clone().amount(Amount::ZERO), BRANCH_ID).is_empty());
+
+        let mut ptx = PartiallySignedTransaction {
+            unsigned_hex: String::new(),
+            hex: crate::bitcoin::consensus::encode::serialize_hex(&tx),
+            inputs: vec![input, PartialInput::new(txid, 1, Script::new())],
+            complete: false,
+        };
+        ptx.inputs[0].signed_by = signers(&tx, 0, &ptx.inputs[0], BRANCH_ID);
+
+        let missing = ptx.missing_signatures();
+        assert_eq!(missing.len(), 2);
+        assert_eq!(missing[0].input, 0);
+        assert_eq!(missing[0].needed, 1);
+        assert_eq!(missing[0].keys, vec![keys[0], keys[1]]);
+        // the single key input has no signature yet
+        assert_eq!(missing[1].input, 1);
+        assert_eq!(missing[1].needed, 1);
+        assert!(missing[1].keys.is_empty());
+
+        ptx.inputs[0].signed_by.push(keys[0]);
+        assert_eq!(ptx.missing_signatures().len(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Waiting on the asynchronous operations that `sendcurrency` and the `z_` calls return an opid for.
+
+use crate::bitcoin::Txid;
+use crate::client::{Result, RpcApi};
+use crate::error::Error;
+use crate::json::{ZOperationStatus, ZOperationStatusResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Why an operation did not result in a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OperationError {
+    Failed {
+        opid: String,
+        code: i32,
+        message: String,
+    },
+    Cancelled(String),
+    /// The daemon does not know the opid, or its result was already removed.
+    NotFound(String),
+    TimedOut(String),
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperationError::Failed {
+                opid,
+                code,
+                message,
+            } => write!(f, "operation {} failed ({}): {}", opid, code, message),
+            OperationError::Cancelled(opid) => write!(f, "operation {} was cancelled", opid),
+            OperationError::NotFound(opid) => write!(f, "operation {} not found", opid),
+            OperationError::TimedOut(opid) => write!(f, "operation {} timed out", opid),
+        }
+    }
+}
+
+impl std::error::Error for OperationError {}
+
+/// Polls the daemon until operations finish, then clears them with `z_getoperationresult`.
+pub struct OperationTracker<'a, C: RpcApi> {
+    client: &'a C,
+    timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl<'a, C: RpcApi> OperationTracker<'a, C> {
+    /// Creates a tracker that polls every second and gives up after 5 minutes.
+    pub fn new(client: &'a C) -> Self {
+        OperationTracker {
+            client,
+            timeout: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Waits for a single operation and returns the txid it resulted in.
+    pub fn wait(&self, opid: &str) -> Result<Txid> {
+        self.wait_all(&[opid])?
+            .remove(opid)
+            .unwrap_or_else(|| Err(OperationError::NotFound(opid.to_string())))
+            .map_err(Error::Operation)
+    }
+
+    /// Waits for all operations to finish or the timeout to pass, whichever comes first.
+    ///
+    /// Every opid gets an outcome; only RPC errors fail the call as a whole.
+    pub fn wait_all(
+        &self,
+        opids: &[&str],
+    ) -> Result<HashMap<String, std::result::Result<Txid, OperationError>>> {
+        let started = Instant::now();
+        let mut outcomes = HashMap::new();
+        let mut pending: Vec<&str> = opids.to_vec();
+
+        loop {
+            let statuses = self.client.z_get_operation_status(pending.clone())?;
+            let mut finished = vec![];
+
+            for opid in pending.iter() {
+                match statuses.iter().flatten().find(|status| status.id == *opid) {
+                    Some(status) if status.status.is_finished() => finished.push(*opid),
+                    Some(_) => {}
+                    None => {
+                        outcomes.insert(
+                            opid.to_string(),
+                            Err(OperationError::NotFound(opid.to_string())),
+                        );
+                    }
+                }
+            }
+
+            if !finished.is_empty() {
+                for result in self.client.z_get_operation_result(finished)? {
+                    outcomes.insert(result.id.clone(), outcome(&result));
+                }
+            }
+
+            pending.retain(|opid| !outcomes.contains_key(*opid));
+
+            if pending.is_empty() {
+                return Ok(outcomes);
+            }
+
+            if started.elapsed() >= self.timeout {
+                for opid in pending {
+                    outcomes.insert(
+                        opid.to_string(),
+                        Err(OperationError::TimedOut(opid.to_string())),
+                    );
+                }
+
+                return Ok(outcomes);
+            }
+
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+fn outcome(result: &ZOperationStatusResult) -> std::result::Result<Txid, OperationError> {
+    match result.status {
+        ZOperationStatus::Success => match &result.result {
+            Some(txid) => Ok(txid.txid),
+            None => Err(OperationError::Failed {
+                opid: result.id.clone(),
+                code: 0,
+                message: String::from("operation succeeded without a txid"),
+            }),
+        },
+        ZOperationStatus::Cancelled => Err(OperationError::Cancelled(result.id.clone())),
+        _ => {
+            let (code, message) = match &result.error {
+                Some(error) => (error.code, error.message.clone()),
+                None => (0, String::new()),
+            };
+            Err(OperationError::Failed {
+                opid: result.id.clone(),
+                code,
+                message,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_client::FakeClient;
+    use serde_json::{json, Value};
+    use std::cell::Cell;
+
+    const TXID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+
+    // An operation in the shape `z_getoperationstatus` returns it.
+    fn status(opid: &str, status: &str) -> Value {
+        let mut status = json!({
+            "id": opid,
+            "status": status,
+            "creation_time": 1_700_000_000,
+            "method": "sendcurrency",
+            "params": [{ "address": "R9HC5WtHbpoa51NCUAz86XLCmGTbkf45NT", "amount": 1.0 }]
+        });
+        match status["status"].as_str() {
+            Some("success") => status["result"] = json!({ "txid": TXID }),
+            Some("failed") => {
+                status["error"] = json!({ "code": -6, "message": "Insufficient funds" })
+            }
+            _ => {}
+        }
+        status
+    }
+
+    #[test]
+    fn waits_for_outcomes() {
+        let polls = Cell::new(0);
+        let current = |opid: &str| match opid {
+            "opid-success" if polls.get() < 2 => Some(status(opid, "executing")),
+            "opid-success" => Some(status(opid, "success")),
+            "opid-failed" => Some(status(opid, "failed")),
+            "opid-stuck" => Some(status(opid, "executing")),
+            _ => None,
+        };
+        let client = FakeClient::new(|method: &str, args: &[Value]| {
+            let opids = args[0].as_array().unwrap().iter();
+            let statuses = opids.filter_map(|opid| current(opid.as_str().unwrap()));
+            if method == "z_getoperationstatus" {
+                polls.set(polls.get() + 1);
+            }
+            Value::Array(statuses.collect())
+        });
+
+        let outcomes = OperationTracker::new(&client)
+            .timeout(Duration::from_millis(50))
+            .poll_interval(Duration::from_millis(1))
+            .wait_all(&["opid-success", "opid-failed", "opid-stuck", "opid-unknown"])
+            .unwrap();
+
+        assert_eq!(outcomes["opid-success"], Ok(TXID.parse().unwrap()));
+        assert_eq!(
+            outcomes["opid-failed"],
+            Err(OperationError::Failed {
+                opid: String::from("opid-failed"),
+                code: -6,
+                message: String::from("Insufficient funds"),
+            })
+        );
+        assert_eq!(
+            outcomes["opid-stuck"],
+            Err(OperationError::TimedOut(String::from("opid-stuck")))
+        );
+        assert_eq!(
+            outcomes["opid-unknown"],
+            Err(OperationError::NotFound(String::from("opid-unknown")))
+        );
+
+        // only finished operations are cleared, each once
+        assert_eq!(
+            client.calls("z_getoperationresult"),
+            vec![vec![json!(["opid-failed"])], vec![json!(["opid-success"])]]
+        );
+    }
+}
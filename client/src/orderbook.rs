@@ -0,0 +1,354 @@
+//! An order book view over the offers returned by `getoffers`.
+//!
+//! Offers are normalized into bids and asks per pair. An identity traded for a currency is always
+//! the base of its pair, with the currency as quote. Offers between two currencies use the queried
+//! currency as base. Prices are expressed in the quote currency per whole unit of base.
+
+use crate::bitcoin::Txid;
+use crate::client::{Result, RpcApi};
+use crate::json::identity::{MarketplaceOffer, OfferVariant};
+use crate::json::vrsc::{Address, Amount};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// What is traded on one side of a pair.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Asset {
+    /// A currency, by its i-address as used in offers.
+    Currency(String),
+    Identity(Address),
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Asset::Currency(currency) => write!(f, "{}", currency),
+            Asset::Identity(identity) => write!(f, "{}", identity),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// The maker offers the quote currency to get the base.
+    Bid,
+    /// The maker offers the base to get the quote currency.
+    Ask,
+}
+
+#[derive(Clone, Debug)]
+pub struct BookEntry {
+    pub side: Side,
+    /// Amount of quote currency per whole unit of base.
+    pub price: Amount,
+    /// Amount of base traded. An identity counts as one whole unit.
+    pub quantity: Amount,
+    pub blockexpiry: u64,
+    pub txid: Txid,
+}
+
+#[derive(Clone, Debug)]
+pub struct PairBook {
+    pub base: Asset,
+    pub quote: String,
+    /// Sorted from highest to lowest price.
+    pub bids: Vec<BookEntry>,
+    /// Sorted from lowest to highest price.
+    pub asks: Vec<BookEntry>,
+}
+
+impl PairBook {
+    fn new(base: Asset, quote: String) -> Self {
+        PairBook {
+            base,
+            quote,
+            bids: vec![],
+            asks: vec![],
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<&BookEntry> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&BookEntry> {
+        self.asks.first()
+    }
+
+    /// The difference between the best ask and the best bid. Negative when the book is crossed.
+    pub fn spread(&self) -> Option<i64> {
+        Some(self.best_ask()?.price.as_sat() as i64 - self.best_bid()?.price.as_sat() as i64)
+    }
+
+    /// The total quantity of base on one side of the book.
+    pub fn depth(&self, side: Side) -> Amount {
+        self.entries(side)
+            .iter()
+            .fold(Amount::ZERO, |acc, entry| acc + entry.quantity)
+    }
+
+    /// The total quantity of base on one side of the book that is offered at `price` or better.
+    pub fn depth_at(&self, side: Side, price: Amount) -> Amount {
+        self.entries(side)
+            .iter()
+            .take_while(|entry| match side {
+                Side::Bid => entry.price >= price,
+                Side::Ask => entry.price <= price,
+            })
+            .fold(Amount::ZERO, |acc, entry| acc + entry.quantity)
+    }
+
+    pub fn entries(&self, side: Side) -> &[BookEntry] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn sort(&mut self) {
+        self.bids.sort_by_key(|entry| Reverse(entry.price));
+        self.asks.sort_by_key(|entry| entry.price);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OrderBook {
+    /// The height against which expired offers were removed.
+    pub height: u64,
+    pub pairs: HashMap<(Asset, String), PairBook>,
+}
+
+impl OrderBook {
+    /// Builds an order book from the result of `getoffers`, leaving out offers that expired at `height`.
+    ///
+    /// `base_currency` is the i-address of the currency that was queried. It is needed to
+    /// normalize offers between two currencies; without it, those offers are left out.
+    pub fn from_offers(
+        offers: &HashMap<String, Vec<MarketplaceOffer>>,
+        base_currency: Option<&str>,
+        height: u64,
+    ) -> Self {
+        let mut pairs: HashMap<(Asset, String), PairBook> = HashMap::new();
+        let mut seen = HashSet::new();
+
+        for marketplace_offer in offers.values().flatten() {
+            let offer = &marketplace_offer.offer;
+
+            if offer.blockexpiry != 0 && offer.blockexpiry <= height {
+                continue;
+            }
+            if !seen.insert(offer.txid) {
+                continue;
+            }
+
+            let (base, quote, side, price, quantity) =
+                match normalize(&offer.offer, &offer.accept, base_currency) {
+                    Some(normalized) => normalized,
+                    None => continue,
+                };
+            let entry = BookEntry {
+                side,
+                price,
+                quantity,
+                blockexpiry: offer.blockexpiry,
+                txid: offer.txid,
+            };
+
+            let book = pairs
+                .entry((base.clone(), quote.clone()))
+                .or_insert_with(|| PairBook::new(base, quote));
+            match side {
+                Side::Bid => book.bids.push(entry),
+                Side::Ask => book.asks.push(entry),
+            }
+        }
+
+        for book in pairs.values_mut() {
+            book.sort();
+        }
+
+        OrderBook { height, pairs }
+    }
+
+    /// Queries the daemon for the offers of a currency or identity and builds the order book
+    /// at the current height.
+    pub fn fetch<C: RpcApi>(client: &C, currency_or_id: &str, is_currency: bool) -> Result<Self> {
+        let base_currency = match is_currency {
+            true => Some(client.get_currency(currency_or_id)?.currencyid.to_string()),
+            false => None,
+        };
+        let offers = client.get_offers(currency_or_id, is_currency, false)?;
+        let height = client.get_block_count()? as u64;
+
+        Ok(OrderBook::from_offers(
+            &offers,
+            base_currency.as_deref(),
+            height,
+        ))
+    }
+
+    pub fn pair(&self, base: &Asset, quote: &str) -> Option<&PairBook> {
+        self.pairs.get(&(base.clone(), quote.to_string()))
+    }
+}
+
+enum Leg {
+    Currency(String, Amount),
+    Identity(Address),
+}
+
+fn leg(variant: &OfferVariant) -> Option<Leg> {
+    match variant {
+        OfferVariant::CurrencyOffer(map) if map.len() == 1 => {
+            let (currency, amount) = map.iter().next()?;
+            Some(Leg::Currency(
+                currency.clone(),
+                Amount::from_vrsc(*amount).ok()?,
+            ))
+        }
+        OfferVariant::CurrencyOffer(_) => None,
+        OfferVariant::IdentityOffer(identity) => {
+            Some(Leg::Identity(identity.identityaddress.clone()))
+        }
+    }
+}
+
+/// Quote amount per whole unit of base.
+fn price(quote_amount: Amount, base_amount: Amount) -> Option<Amount> {
+    if base_amount == Amount::ZERO {
+        return None;
+    }
+    let price = quote_amount.as_sat() as u128 * Amount::ONE_VRSC.as_sat() as u128
+        / base_amount.as_sat() as u128;
+
+    Some(Amount::from_sat(u64::try_from(price).ok()?))
+}
+
+fn normalize(
+    offered: &OfferVariant,
+    accepted: &OfferVariant,
+    base_currency: Option<&str>,
+) -> Option<(Asset, String, Side, Amount, Amount)> {
+    match (leg(offered)?, leg(accepted)?) {
+        (Leg::Identity(id), Leg::Currency(quote, amount)) => Some((
+            Asset::Identity(id),
+            quote,
+            Side::Ask,
+            amount,
+            Amount::ONE_VRSC,
+        )),
+        (Leg::Currency(quote, amount), Leg::Identity(id)) => Some((
+            Asset::Identity(id),
+            quote,
+            Side::Bid,
+            amount,
+            Amount::ONE_VRSC,
+        )),
+        (Leg::Currency(offered, offered_amount), Leg::Currency(accepted, accepted_amount)) => {
+            let base = base_currency?;
+            if offered == base {
+                Some((
+                    Asset::Currency(offered),
+                    accepted,
+                    Side::Ask,
+                    price(accepted_amount, offered_amount)?,
+                    offered_amount,
+                ))
+            } else if accepted == base {
+                Some((
+                    Asset::Currency(accepted),
+                    offered,
+                    Side::Bid,
+                    price(offered_amount, accepted_amount)?,
+                    accepted_amount,
+                ))
+            } else {
+                None
+            }
+        }
+        // identity for identity swaps have no price
+        (Leg::Identity(_), Leg::Identity(_)) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VRSC: &str = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+    const TBTC: &str = "iS8TfRPfVpKo5FVfSUzfHBQxo9KuzpnqLU";
+
+    fn currency_offer(
+        give: (&str, f64),
+        get: (&str, f64),
+        expiry: u64,
+        txid: &str,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "identityid": "iJhCezBExJHvtyH3fGhNnt2NhU4Ztkf2yq",
+            "price": (get.1 / give.1 * 1e8).round() / 1e8,
+            "offer": {
+                "offer": { give.0: give.1 },
+                "accept": { get.0: get.1 },
+                "blockexpiry": expiry,
+                "txid": txid
+            }
+        })
+    }
+
+    #[test]
+    fn builds_sorted_book_without_expired_offers() {
+        let offers: Vec<MarketplaceOffer> = serde_json::from_value(serde_json::json!([
+            currency_offer(
+                (VRSC, 10.0),
+                (TBTC, 0.002),
+                2000,
+                "1111111111111111111111111111111111111111111111111111111111111111"
+            ),
+            currency_offer(
+                (VRSC, 5.0),
+                (TBTC, 0.0015),
+                2000,
+                "2222222222222222222222222222222222222222222222222222222222222222"
+            ),
+            currency_offer(
+                (TBTC, 0.001),
+                (VRSC, 10.0),
+                2000,
+                "3333333333333333333333333333333333333333333333333333333333333333"
+            ),
+            currency_offer(
+                (TBTC, 0.003),
+                (VRSC, 20.0),
+                2000,
+                "4444444444444444444444444444444444444444444444444444444444444444"
+            ),
+            currency_offer(
+                (VRSC, 1.0),
+                (TBTC, 0.0001),
+                1000,
+                "5555555555555555555555555555555555555555555555555555555555555555"
+            ),
+        ]))
+        .unwrap();
+        let mut map = HashMap::new();
+        map.insert(String::from("currency_offers"), offers);
+
+        let book = OrderBook::from_offers(&map, Some(VRSC), 1500);
+        let pair = book.pair(&Asset::Currency(VRSC.to_string()), TBTC).unwrap();
+
+        assert_eq!(pair.asks.len(), 2);
+        assert_eq!(pair.best_ask().unwrap().price, Amount::from_sat(20_000));
+        assert_eq!(pair.asks[1].price, Amount::from_sat(30_000));
+        assert_eq!(pair.bids.len(), 2);
+        assert_eq!(pair.best_bid().unwrap().price, Amount::from_sat(15_000));
+        assert_eq!(pair.bids[1].price, Amount::from_sat(10_000));
+        assert_eq!(pair.spread(), Some(5_000));
+        assert_eq!(pair.depth(Side::Ask), Amount::from_vrsc(15.0).unwrap());
+        assert_eq!(
+            pair.depth_at(Side::Bid, Amount::from_sat(15_000)),
+            Amount::from_vrsc(20.0).unwrap()
+        );
+    }
+}
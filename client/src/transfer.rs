@@ -0,0 +1,367 @@
+//! Following a reserve transfer from the chain it was sent on to its import on the destination
+//! system.
+//!
+//! A reserve transfer is spent by the export that includes it. Once the destination system has
+//! confirmed a notarization of the source chain at or past the export height, the export can be
+//! imported, after which the import refers back to the export by its txid.
+
+use crate::bitcoin::Txid;
+use crate::client::{Result, RpcApi};
+use crate::error::Error;
+use crate::json::vrsc::Address;
+use crate::json::GetRawTransactionScriptPubKeyReserveTransfer;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransferStage {
+    /// Waiting on the source chain to be exported.
+    Pending,
+    /// Included in an export, which the destination system has not notarized yet.
+    Exported,
+    /// The export is covered by a confirmed notarization on the destination system.
+    Notarized,
+    Imported,
+}
+
+/// Where an export or import was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferLocation {
+    pub height: u64,
+    pub txid: Txid,
+}
+
+#[derive(Clone, Debug)]
+pub struct TransferStatus {
+    pub stage: TransferStage,
+    pub transfer: GetRawTransactionScriptPubKeyReserveTransfer,
+    /// The height of the transaction that sent the transfer, if it is mined.
+    pub height: Option<u64>,
+    /// The export on the source chain.
+    pub export: Option<TransferLocation>,
+    /// The latest height of the source chain that the destination system confirmed.
+    pub notarized_height: Option<u64>,
+    /// The import on the destination system.
+    pub import: Option<TransferLocation>,
+}
+
+/// Tracks transfers between two chains, with a client for each.
+pub struct TransferTracker<'a, S: RpcApi, D: RpcApi> {
+    source: &'a S,
+    destination: &'a D,
+    import_start_height: Option<u64>,
+}
+
+impl<'a, S: RpcApi, D: RpcApi> TransferTracker<'a, S, D> {
+    pub fn new(source: &'a S, destination: &'a D) -> Self {
+        TransferTracker {
+            source,
+            destination,
+            import_start_height: None,
+        }
+    }
+
+    /// The height on the destination system from where to look for imports. Imports are searched
+    /// from the start of the chain by default.
+    pub fn import_start_height(mut self, height: u64) -> Self {
+        self.import_start_height = Some(height);
+        self
+    }
+
+    /// Finds how far the reserve transfer in output `vout` of `txid` on the source chain got.
+    pub fn status(&self, txid: &Txid, vout: u32) -> Result<TransferStatus> {
+        let tx = self.source.get_raw_transaction_verbose(txid)?;
+        let output = tx
+            .vout
+            .iter()
+            .find(|output| output.n == vout)
+            .ok_or(Error::NotAReserveTransfer(*txid, vout))?;
+        let transfer = output
+            .script_pubkey
+            .reservetransfer
+            .clone()
+            .ok_or(Error::NotAReserveTransfer(*txid, vout))?;
+        let height = tx.height.filter(|height| *height > 0).map(|h| h as u64);
+
+        let mut status = TransferStatus {
+            stage: TransferStage::Pending,
+            transfer,
+            height,
+            export: None,
+            notarized_height: None,
+            import: None,
+        };
+
+        let spent_by = match output.spent_tx_id.or(status.transfer.spenttxid) {
+            Some(spent_by) => spent_by,
+            None => return Ok(status),
+        };
+
+        let source_system = self.source.get_blockchain_info()?.chainid;
+        let destination_system = self.destination.get_blockchain_info()?.chainid;
+
+        status.export = self.find_export(&destination_system, &spent_by, height)?;
+        let export = match status.export {
+            Some(export) => export,
+            // spent by something other than an export to this destination
+            None => return Ok(status),
+        };
+        status.stage = TransferStage::Exported;
+
+        let last_import = self
+            .destination
+            .get_last_import_from(&source_system.to_string())?;
+        status.notarized_height = last_import
+            .lastconfirmednotarization
+            .map(|notarization| notarization.notarizationheight);
+        if matches!(status.notarized_height, Some(notarized) if notarized >= export.height) {
+            status.stage = TransferStage::Notarized;
+        }
+
+        status.import = self.find_import(&source_system, &export.txid)?;
+        if status.import.is_some() {
+            status.stage = TransferStage::Imported;
+        }
+
+        Ok(status)
+    }
+
+    fn find_export(
+        &self,
+        destination_system: &Address,
+        txid: &Txid,
+        from_height: Option<u64>,
+    ) -> Result<Option<TransferLocation>> {
+        let exports =
+            self.source
+                .get_exports(&destination_system.to_string(), from_height, None)?;
+
+        Ok(exports
+            .into_iter()
+            .find(|export| &export.txid == txid)
+            .map(|export| TransferLocation {
+                height: export.height,
+                txid: export.txid,
+            }))
+    }
+
+    fn find_import(
+        &self,
+        source_system: &Address,
+        export_txid: &Txid,
+    ) -> Result<Option<TransferLocation>> {
+        let imports = self.destination.get_imports(
+            &source_system.to_string(),
+            self.import_start_height,
+            None,
+        )?;
+
+        Ok(imports
+            .into_iter()
+            .find(|import| &import.import.exporttxid == export_txid)
+            .map(|import| TransferLocation {
+                height: import.importheight,
+                txid: import.importtxid,
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_client::FakeClient;
+    use serde_json::{json, Value};
+
+    const VRSC: &str = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+    const PBAAS: &str = "iCkKJuJScy4Z6NSDK7Mt42ZAB2NEnAE1o4";
+    const ADDRESS: &str = "R9HC5WtHbpoa51NCUAz86XLCmGTbkf45NT";
+    const TRANSFER_TXID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+    const EXPORT_TXID: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+    const OTHER_TXID: &str = "3333333333333333333333333333333333333333333333333333333333333333";
+    const IMPORT_TXID: &str = "4444444444444444444444444444444444444444444444444444444444444444";
+    const SPENDING_TXID: &str = "5555555555555555555555555555555555555555555555555555555555555555";
+
+    // The responses below are built in the shapes the daemon returns them, not recorded.
+
+    fn blockchain_info(chainid: &str) -> Value {
+        json!({
+            "chain": "main",
+            "name": "VRSC",
+            "chainid": chainid,
+            "blocks": 200,
+            "headers": 200,
+            "bestblockhash": TRANSFER_TXID,
+            "difficulty": 1.0,
+            "verificationprogress": 1.0,
+            "chainwork": "",
+            "chainstake": "",
+            "pruned": false,
+            "size_on_disk": 0,
+            "commitments": 0,
+            "valuePools": [],
+            "softforks": [],
+            "consensus": { "chaintip": "76b809bb", "nextblock": "76b809bb" }
+        })
+    }
+
+    // The transaction that sent the transfer, with where its output was spent as reported by
+    // the spent index on the output, or by the transfer itself.
+    fn transfer_tx(spent_tx_id: Option<&str>, spenttxid: Option<&str>) -> Value {
+        json!({
+            "hex": "",
+            "txid": TRANSFER_TXID,
+            "overwintered": true,
+            "version": 4,
+            "versiongroupid": "892f2085",
+            "locktime": 0,
+            "expiryheight": 120,
+            "vin": [],
+            "vout": [{
+                "value": 0.0,
+                "valueSat": 0,
+                "n": 0,
+                "scriptPubKey": {
+                    "asm": "",
+                    "hex": "",
+                    "type": "cryptocondition",
+                    "spendableoutput": true,
+                    "reservetransfer": {
+                        "version": 1,
+                        "currencyvalues": { VRSC: 10.0 },
+                        "flags": 1,
+                        "feecurrencyid": VRSC,
+                        "fees": 0.0002,
+                        "destinationcurrencyid": PBAAS,
+                        "destination": { "type": 4, "address": ADDRESS },
+                        "spenttxid": spenttxid
+                    }
+                },
+                "spentTxId": spent_tx_id
+            }],
+            "vjoinsplit": [],
+            "height": 100
+        })
+    }
+
+    fn export(txid: &str, height: u64) -> Value {
+        json!({
+            "height": height,
+            "txid": txid,
+            "txoutnum": 1,
+            "exportinfo": {
+                "version": 1,
+                "sourcesystemid": VRSC,
+                "hashtransfers": "",
+                "destinationsystemid": PBAAS,
+                "destinationcurrencyid": PBAAS,
+                "sourceheightstart": 90,
+                "sourceheightend": height,
+                "numinputs": 1
+            }
+        })
+    }
+
+    fn last_import(notarization_height: u64) -> Value {
+        json!({
+            "lastconfirmednotarization": {
+                "version": 1,
+                "proposer": { "address": ADDRESS, "type": 2 },
+                "currencyid": VRSC,
+                "notarizationheight": notarization_height,
+                "currencystate": {
+                    "flags": 48,
+                    "version": 1,
+                    "currencyid": VRSC,
+                    "initialsupply": 0.0,
+                    "emitted": 0.0,
+                    "supply": 0.0,
+                    "primarycurrencyfees": 0.0,
+                    "primarycurrencyconversionfees": 0.0,
+                    "primarycurrencyout": 0.0,
+                    "preconvertedout": 0.0
+                },
+                "prevnotarizationtxid": OTHER_TXID,
+                "prevnotarizationout": 0,
+                "prevheight": 80,
+                "hashprevcrossnotarization": "",
+                "currencystates": [],
+                "proofroots": [],
+                "nodes": []
+            }
+        })
+    }
+
+    fn import(export_txid: &str) -> Value {
+        json!({
+            "importheight": 30,
+            "importtxid": IMPORT_TXID,
+            "import": {
+                "version": 1,
+                "flags": 1,
+                "sourcesystemid": VRSC,
+                "sourceheight": 110,
+                "importcurrencyid": PBAAS,
+                "valuein": {},
+                "tokensout": {},
+                "numoutputs": 1,
+                "hashtransfers": "",
+                "exporttxid": export_txid
+            }
+        })
+    }
+
+    fn status(tx: Value, notarization_height: u64, imported: bool) -> TransferStatus {
+        let source = FakeClient::new(|method: &str, _: &[Value]| match method {
+            "getrawtransaction" => tx.clone(),
+            "getblockchaininfo" => blockchain_info(VRSC),
+            "getexports" => json!([export(OTHER_TXID, 105), export(EXPORT_TXID, 110)]),
+            _ => panic!("unexpected call to {} on the source", method),
+        });
+        let destination = FakeClient::new(|method: &str, _: &[Value]| match method {
+            "getblockchaininfo" => blockchain_info(PBAAS),
+            "getlastimportfrom" => last_import(notarization_height),
+            "getimports" if imported => json!([import(OTHER_TXID), import(EXPORT_TXID)]),
+            "getimports" => json!([import(OTHER_TXID)]),
+            _ => panic!("unexpected call to {} on the destination", method),
+        });
+
+        TransferTracker::new(&source, &destination)
+            .status(&TRANSFER_TXID.parse().unwrap(), 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn follows_transfer_to_import() {
+        let pending = status(transfer_tx(None, None), 0, false);
+        assert_eq!(pending.stage, TransferStage::Pending);
+        assert_eq!(pending.height, Some(100));
+        assert_eq!(pending.export, None);
+
+        // spent, but not by an export to the destination
+        let spent = status(transfer_tx(Some(SPENDING_TXID), None), 0, false);
+        assert_eq!(spent.stage, TransferStage::Pending);
+
+        let export = Some(TransferLocation {
+            height: 110,
+            txid: EXPORT_TXID.parse().unwrap(),
+        });
+        let exported = status(transfer_tx(Some(EXPORT_TXID), None), 109, false);
+        assert_eq!(exported.stage, TransferStage::Exported);
+        assert_eq!(exported.export, export);
+        assert_eq!(exported.notarized_height, Some(109));
+
+        // the transfer's own spenttxid is used when the output has no spent index
+        let notarized = status(transfer_tx(None, Some(EXPORT_TXID)), 110, false);
+        assert_eq!(notarized.stage, TransferStage::Notarized);
+        assert_eq!(notarized.export, export);
+        assert_eq!(notarized.import, None);
+
+        let imported = status(transfer_tx(Some(EXPORT_TXID), None), 120, true);
+        assert_eq!(imported.stage, TransferStage::Imported);
+        assert_eq!(
+            imported.import,
+            Some(TransferLocation {
+                height: 30,
+                txid: IMPORT_TXID.parse().unwrap(),
+            })
+        );
+    }
+}
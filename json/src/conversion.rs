@@ -0,0 +1,282 @@
+//! Offline conversion estimates for fractional basket currencies.
+//!
+//! Uses the fractional reserve formulas on a `CurrencyState` snapshot, so quotes can be made
+//! without asking the daemon. The daemon solves all conversions in a block at one price; these
+//! estimates assume the conversion is the only one in its block. Fees are only deducted from the
+//! input, and what the daemon does with them afterwards is not modelled. The estimates have not
+//! been checked against `estimateconversion`, so use that call when the exact output matters.
+
+use crate::vrsc::{Address, Amount};
+use crate::{CurrencyState, ReserveCurrency};
+
+/// The fee on a conversion between a reserve and the fractional currency: 0.025%.
+pub const CONVERSION_FEE: f64 = 0.00025;
+
+/// The fee on a conversion from one reserve to another, through the fractional currency: 0.05%.
+pub const RESERVE_TO_RESERVE_FEE: f64 = 0.0005;
+
+const SATS_PER_COIN: f64 = 100_000_000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConversionEstimate {
+    pub amount_in: Amount,
+    /// The conversion fee, in the input currency.
+    pub fee: Amount,
+    /// The amount that is actually converted: the input less the fee.
+    pub net_input: Amount,
+    pub amount_out: Amount,
+    /// The effective price, in output currency per unit of input, after fees.
+    pub price: f64,
+}
+
+impl CurrencyState {
+    pub fn reserve(&self, currencyid: &Address) -> Option<&ReserveCurrency> {
+        self.reservecurrencies
+            .as_ref()?
+            .iter()
+            .find(|reserve| &reserve.currencyid == currencyid)
+    }
+
+    /// The price of one unit of this fractional currency in one of its reserves:
+    /// `reserves / (supply * weight)`.
+    pub fn price_in_reserve(&self, reserve: &Address) -> Option<f64> {
+        let reserve = self.reserve(reserve)?;
+        let supply = coins(self.supply);
+        if supply == 0.0 || reserve.weight == 0.0 {
+            return None;
+        }
+
+        Some(coins(reserve.reserves) / (supply * reserve.weight))
+    }
+
+    /// How many units of reserve `to` one unit of reserve `from` is worth at current prices.
+    pub fn via_price(&self, from: &Address, to: &Address) -> Option<f64> {
+        Some(self.price_in_reserve(to)? / self.price_in_reserve(from)?)
+    }
+
+    /// Estimates the output of converting `amount` of `from` into `to`. Either side is the
+    /// fractional currency itself or one of its reserves; two reserves convert through the
+    /// fractional currency.
+    ///
+    /// Returns None if a currency is not part of this basket or the basket cannot convert.
+    pub fn estimate_conversion(
+        &self,
+        from: &Address,
+        to: &Address,
+        amount: Amount,
+    ) -> Option<ConversionEstimate> {
+        let supply = coins(self.supply);
+        if supply == 0.0 || from == to {
+            return None;
+        }
+
+        let (fee_rate, out) = if from == &self.currencyid {
+            let reserve = self.reserve(to)?;
+            let net = net_input(amount, CONVERSION_FEE);
+            (
+                CONVERSION_FEE,
+                sell(coins(net), supply, reserve_coins(reserve), reserve.weight)?,
+            )
+        } else if to == &self.currencyid {
+            let reserve = self.reserve(from)?;
+            let net = net_input(amount, CONVERSION_FEE);
+            (
+                CONVERSION_FEE,
+                buy(coins(net), supply, reserve_coins(reserve), reserve.weight)?,
+            )
+        } else {
+            let from_reserve = self.reserve(from)?;
+            let to_reserve = self.reserve(to)?;
+            let net = net_input(amount, RESERVE_TO_RESERVE_FEE);
+            let fractional = buy(
+                coins(net),
+                supply,
+                reserve_coins(from_reserve),
+                from_reserve.weight,
+            )?;
+            (
+                RESERVE_TO_RESERVE_FEE,
+                sell(
+                    fractional,
+                    supply + fractional,
+                    reserve_coins(to_reserve),
+                    to_reserve.weight,
+                )?,
+            )
+        };
+
+        let net = net_input(amount, fee_rate);
+        let amount_out = Amount::from_sat((out * SATS_PER_COIN).floor() as u64);
+
+        Some(ConversionEstimate {
+            amount_in: amount,
+            fee: amount - net,
+            net_input: net,
+            amount_out,
+            price: match amount {
+                Amount::ZERO => 0.0,
+                _ => coins(amount_out) / coins(amount),
+            },
+        })
+    }
+}
+
+fn coins(amount: Amount) -> f64 {
+    amount.as_sat() as f64 / SATS_PER_COIN
+}
+
+fn reserve_coins(reserve: &ReserveCurrency) -> f64 {
+    coins(reserve.reserves)
+}
+
+fn net_input(amount: Amount, fee_rate: f64) -> Amount {
+    let fee = (amount.as_sat() as f64 * fee_rate).ceil() as u64;
+    Amount::from_sat(amount.as_sat().saturating_sub(fee))
+}
+
+/// Fractional currency minted for `reserve_in`: `supply * ((1 + reserve_in / reserves)^weight - 1)`.
+fn buy(reserve_in: f64, supply: f64, reserves: f64, weight: f64) -> Option<f64> {
+    if reserves <= 0.0 || weight <= 0.0 {
+        return None;
+    }
+
+    Some(supply * ((1.0 + reserve_in / reserves).powf(weight) - 1.0))
+}
+
+/// Reserve released for burning `fractional_in`: `reserves * (1 - (1 - fractional_in / supply)^(1 / weight))`.
+fn sell(fractional_in: f64, supply: f64, reserves: f64, weight: f64) -> Option<f64> {
+    if fractional_in >= supply || weight <= 0.0 {
+        return None;
+    }
+
+    Some(reserves * (1.0 - (1.0 - fractional_in / supply).powf(1.0 / weight)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const BASKET: &str = "iCkKJuJScy4Z6NSDK7Mt42ZAB2NEnAE1o4";
+    const VRSC: &str = "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV";
+    const TBTC: &str = "iS8TfRPfVpKo5FVfSUzfHBQxo9KuzpnqLU";
+
+    // A `currencystate` in the shape `getcurrencystate` returns it, for a basket with two
+    // reserves at equal weight.
+    fn state() -> CurrencyState {
+        serde_json::from_value(serde_json::json!({
+            "flags": 49,
+            "version": 1,
+            "currencyid": BASKET,
+            "reservecurrencies": [
+                {
+                    "currencyid": VRSC,
+                    "weight": 0.5,
+                    "reserves": 100000.0,
+                    "priceinreserve": 1.0
+                },
+                {
+                    "currencyid": TBTC,
+                    "weight": 0.5,
+                    "reserves": 10.0,
+                    "priceinreserve": 0.0001
+                }
+            ],
+            "initialsupply": 200000.0,
+            "emitted": 0.0,
+            "supply": 200000.0,
+            "primarycurrencyfees": 0.0,
+            "primarycurrencyconversionfees": 0.0,
+            "primarycurrencyout": 0.0,
+            "preconvertedout": 0.0
+        }))
+        .unwrap()
+    }
+
+    fn address(s: &str) -> Address {
+        Address::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn price_in_reserve_matches_state() {
+        let state = state();
+        for reserve in state.reservecurrencies.as_ref().unwrap() {
+            let price = state.price_in_reserve(&reserve.currencyid).unwrap();
+            assert!((price - coins(reserve.priceinreserve)).abs() < 1e-8);
+        }
+        let via = state.via_price(&address(VRSC), &address(TBTC)).unwrap();
+        assert!((via - 0.0001).abs() < 1e-12);
+    }
+
+    // A basket with uneven weights and reserves, so that no conversion lands on a round number.
+    // The expected outputs below pin the formulas above; they are not what the daemon returns.
+    fn uneven_state() -> CurrencyState {
+        serde_json::from_value(serde_json::json!({
+            "flags": 49,
+            "version": 1,
+            "currencyid": BASKET,
+            "reservecurrencies": [
+                {
+                    "currencyid": VRSC,
+                    "weight": 0.6,
+                    "reserves": 876543.21098765,
+                    "priceinreserve": 1.18333334
+                },
+                {
+                    "currencyid": TBTC,
+                    "weight": 0.4,
+                    "reserves": 12.34567891,
+                    "priceinreserve": 0.00002500
+                }
+            ],
+            "initialsupply": 1000000.0,
+            "emitted": 0.0,
+            "supply": 1234567.89012345,
+            "primarycurrencyfees": 0.0,
+            "primarycurrencyconversionfees": 0.0,
+            "primarycurrencyout": 0.0,
+            "preconvertedout": 0.0
+        }))
+        .unwrap()
+    }
+
+    // Outputs are floored to whole satoshis, so allow one satoshi for rounding of the powers.
+    fn assert_sats(amount: Amount, expected: u64) {
+        assert!(
+            amount.as_sat().abs_diff(expected) <= 1,
+            "{} != {}",
+            amount.as_sat(),
+            expected
+        );
+    }
+
+    #[test]
+    fn estimates_conversions() {
+        let state = uneven_state();
+        let basket = address(BASKET);
+        let vrsc = address(VRSC);
+        let tbtc = address(TBTC);
+
+        let buy = state
+            .estimate_conversion(&vrsc, &basket, Amount::from_vrsc(250.0).unwrap())
+            .unwrap();
+        assert_eq!(buy.fee, Amount::from_sat(6_250_000));
+        assert_sats(buy.amount_out, 21_120_274_301);
+
+        let sell = state
+            .estimate_conversion(&basket, &vrsc, Amount::from_vrsc(1000.0).unwrap())
+            .unwrap();
+        assert_eq!(sell.fee, Amount::from_sat(25_000_000));
+        assert_sats(sell.amount_out, 118_271_814_312);
+
+        let via = state
+            .estimate_conversion(&vrsc, &tbtc, Amount::from_vrsc(5000.0).unwrap())
+            .unwrap();
+        assert_eq!(via.fee, Amount::from_vrsc(2.5).unwrap());
+        assert_sats(via.amount_out, 10_483_351);
+
+        assert!(state
+            .estimate_conversion(&vrsc, &vrsc, Amount::ONE_VRSC)
+            .is_none());
+    }
+}
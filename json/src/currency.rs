@@ -0,0 +1,919 @@
+use bitflags::bitflags;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::{collections::HashMap, fmt};
+
+use crate::vrsc::Amount;
+use crate::{
+    CurrencyState, CurrencyStateConversionDataPair, GetCurrencyResult, GetCurrencyStateResult,
+    GetCurrencyStateResultInner,
+};
+
+bitflags! {
+    /// The `options` of a currency definition.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct CurrencyOptions: u32 {
+        const FRACTIONAL = 0x01;
+        /// Only the controlling identity can issue sub-IDs.
+        const ID_RESTRICTED = 0x02;
+        const ID_STAKING = 0x04;
+        const ID_REFERRALS = 0x08;
+        const ID_REFERRAL_REQUIRED = 0x10;
+        const TOKEN = 0x20;
+        const SINGLE_CURRENCY = 0x40;
+        const GATEWAY = 0x80;
+        const PBAAS = 0x100;
+        const GATEWAY_CONVERTER = 0x200;
+        const GATEWAY_NAME_CONTROLLER = 0x400;
+        const NFT_TOKEN = 0x800;
+
+        const _ = !0;
+    }
+}
+
+bitflags! {
+    /// The `flags` of a currency state.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct CurrencyStateFlags: u16 {
+        const FRACTIONAL = 0x01;
+        const REFUNDING = 0x02;
+        const PRELAUNCH = 0x04;
+        const LAUNCH_CLEAR = 0x08;
+        const LAUNCH_CONFIRMED = 0x10;
+        const LAUNCH_COMPLETE = 0x20;
+
+        const _ = !0;
+    }
+}
+
+macro_rules! serde_bits_impl {
+    ($name:ident, $bits:ty) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.bits().serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Ok($name::from_bits_retain(<$bits>::deserialize(deserializer)?))
+            }
+        }
+    };
+}
+serde_bits_impl!(CurrencyOptions, u32);
+serde_bits_impl!(CurrencyStateFlags, u16);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum NotarizationProtocol {
+    Invalid,
+    /// Notarized by the block producers of the chain itself.
+    Auto,
+    /// Confirmed by the notaries of the currency.
+    NotaryConfirm,
+    /// Confirmed by the chain ID acting as notary.
+    NotaryChainId,
+    Unknown(u32),
+}
+
+impl From<u32> for NotarizationProtocol {
+    fn from(protocol: u32) -> Self {
+        match protocol {
+            0 => NotarizationProtocol::Invalid,
+            1 => NotarizationProtocol::Auto,
+            2 => NotarizationProtocol::NotaryConfirm,
+            3 => NotarizationProtocol::NotaryChainId,
+            other => NotarizationProtocol::Unknown(other),
+        }
+    }
+}
+
+impl From<NotarizationProtocol> for u32 {
+    fn from(protocol: NotarizationProtocol) -> Self {
+        match protocol {
+            NotarizationProtocol::Invalid => 0,
+            NotarizationProtocol::Auto => 1,
+            NotarizationProtocol::NotaryConfirm => 2,
+            NotarizationProtocol::NotaryChainId => 3,
+            NotarizationProtocol::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum ProofProtocol {
+    Invalid,
+    /// Decentralized, proven with MMR proofs.
+    PbaasMmr,
+    /// Centralized, the chain ID can mint and burn.
+    ChainId,
+    /// Proven through Ethereum notarizations.
+    EthNotarization,
+    Unknown(u32),
+}
+
+impl From<u32> for ProofProtocol {
+    fn from(protocol: u32) -> Self {
+        match protocol {
+            0 => ProofProtocol::Invalid,
+            1 => ProofProtocol::PbaasMmr,
+            2 => ProofProtocol::ChainId,
+            3 => ProofProtocol::EthNotarization,
+            other => ProofProtocol::Unknown(other),
+        }
+    }
+}
+
+impl From<ProofProtocol> for u32 {
+    fn from(protocol: ProofProtocol) -> Self {
+        match protocol {
+            ProofProtocol::Invalid => 0,
+            ProofProtocol::PbaasMmr => 1,
+            ProofProtocol::ChainId => 2,
+            ProofProtocol::EthNotarization => 3,
+            ProofProtocol::Unknown(other) => other,
+        }
+    }
+}
+
+/// The `systemtype` filter of `listcurrencies`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemType {
+    Local,
+    Imported,
+    Gateway,
+    Pbaas,
+}
+
+/// The `launchstate` filter of `listcurrencies`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchStateFilter {
+    Prelaunch,
+    Launched,
+    Refund,
+    Complete,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchState {
+    /// Before the start block, or the launch was not yet cleared.
+    Prelaunch,
+    Launched,
+    /// The launch failed and pre-conversions can be refunded.
+    Refunding,
+    /// The launch failed and the currency has completed its refund state.
+    Failed,
+}
+
+impl CurrencyState {
+    pub fn launch_state(&self) -> LaunchState {
+        let flags = self.flags;
+        if flags.contains(CurrencyStateFlags::REFUNDING) {
+            if flags.contains(CurrencyStateFlags::LAUNCH_COMPLETE) {
+                LaunchState::Failed
+            } else {
+                LaunchState::Refunding
+            }
+        } else if flags.contains(CurrencyStateFlags::PRELAUNCH) {
+            LaunchState::Prelaunch
+        } else if flags
+            .intersects(CurrencyStateFlags::LAUNCH_CONFIRMED | CurrencyStateFlags::LAUNCH_COMPLETE)
+        {
+            LaunchState::Launched
+        } else {
+            LaunchState::Prelaunch
+        }
+    }
+}
+
+impl GetCurrencyResult {
+    /// The launch state of this currency at `height`, based on its start block and last
+    /// confirmed currency state.
+    pub fn launch_state(&self, height: u64) -> LaunchState {
+        let state = match self
+            .lastconfirmedcurrencystate
+            .as_ref()
+            .or(self.bestcurrencystate.as_ref())
+        {
+            Some(state) => state.launch_state(),
+            None if height >= self.startblock => LaunchState::Launched,
+            None => LaunchState::Prelaunch,
+        };
+
+        match state {
+            LaunchState::Launched if height < self.startblock => LaunchState::Prelaunch,
+            state => state,
+        }
+    }
+
+    /// Whether the currency stopped at its end block. An end block of 0 means it never ends.
+    pub fn has_ended(&self, height: u64) -> bool {
+        self.endblock != 0 && height >= self.endblock
+    }
+
+    pub fn is_fractional(&self) -> bool {
+        self.options.contains(CurrencyOptions::FRACTIONAL)
+    }
+
+    pub fn is_token(&self) -> bool {
+        self.options.contains(CurrencyOptions::TOKEN)
+    }
+
+    pub fn is_gateway(&self) -> bool {
+        self.options.contains(CurrencyOptions::GATEWAY)
+    }
+
+    pub fn is_pbaas(&self) -> bool {
+        self.options.contains(CurrencyOptions::PBAAS)
+    }
+
+    pub fn is_nft(&self) -> bool {
+        self.options.contains(CurrencyOptions::NFT_TOKEN)
+    }
+}
+
+/// The heights to query in `getcurrencystate`: a single height, or every `step` blocks from
+/// `start` up to and including `end`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurrencyStateRange {
+    pub start: u64,
+    pub end: u64,
+    pub step: Option<u64>,
+}
+
+impl CurrencyStateRange {
+    pub fn at(height: u64) -> Self {
+        CurrencyStateRange {
+            start: height,
+            end: height,
+            step: None,
+        }
+    }
+
+    pub fn new(start: u64, end: u64) -> Self {
+        CurrencyStateRange {
+            start,
+            end,
+            step: None,
+        }
+    }
+
+    pub fn step(mut self, step: u64) -> Self {
+        self.step = Some(step);
+        self
+    }
+}
+
+impl fmt::Display for CurrencyStateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.step {
+            Some(step) => write!(f, "{},{},{}", self.start, self.end, step),
+            None if self.start == self.end => write!(f, "{}", self.start),
+            None => write!(f, "{},{}", self.start, self.end),
+        }
+    }
+}
+
+impl Serialize for CurrencyStateRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl GetCurrencyStateResult {
+    pub fn data(&self) -> Option<&GetCurrencyStateResultInner> {
+        match self {
+            GetCurrencyStateResult::Data(data) => Some(data),
+            GetCurrencyStateResult::TotalVolume { .. } => None,
+        }
+    }
+}
+
+/// The conversion volume of one pair in one interval of a `getcurrencystate` range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candle {
+    pub height: u64,
+    pub blocktime: u64,
+    pub open: Amount,
+    pub high: Amount,
+    pub low: Amount,
+    pub close: Amount,
+    pub volume: Amount,
+}
+
+impl
+    From<(
+        &GetCurrencyStateResultInner,
+        &CurrencyStateConversionDataPair,
+    )> for Candle
+{
+    fn from(
+        (data, pair): (
+            &GetCurrencyStateResultInner,
+            &CurrencyStateConversionDataPair,
+        ),
+    ) -> Self {
+        Candle {
+            height: data.height,
+            blocktime: data.blocktime,
+            open: pair.open,
+            high: pair.high,
+            low: pair.low,
+            close: pair.close,
+            volume: pair.volume,
+        }
+    }
+}
+
+/// Candles per `(currency, convertto)` pair, ordered by height.
+pub type CurrencyStateTimeSeries = HashMap<(String, String), Vec<Candle>>;
+
+/// Collects the conversion data of `getcurrencystate` results into candles per pair, skipping the
+/// total volume entry and intervals without conversion data.
+pub fn time_series<'a, I>(results: I) -> CurrencyStateTimeSeries
+where
+    I: IntoIterator<Item = &'a GetCurrencyStateResult>,
+{
+    let mut series = CurrencyStateTimeSeries::new();
+
+    for data in results.into_iter().filter_map(GetCurrencyStateResult::data) {
+        for pair in data
+            .conversiondata
+            .iter()
+            .flat_map(|conversiondata| conversiondata.volumepairs.iter())
+        {
+            series
+                .entry((pair.currency.clone(), pair.convertto.clone()))
+                .or_default()
+                .push(Candle::from((data, pair)));
+        }
+    }
+
+    for candles in series.values_mut() {
+        candles.sort_by_key(|candle| candle.height);
+    }
+
+    series
+}
+
+/// Why a currency definition would be rejected. Checked locally, before `definecurrency` is called.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CurrencyDefinitionError {
+    EmptyName,
+    /// A fractional currency needs reserve currencies, weights and an initial supply.
+    MissingFractionalField(&'static str),
+    /// A list that must have one entry per reserve currency has a different length.
+    LengthMismatch(&'static str),
+    /// Weights must be positive and sum to 1.
+    InvalidWeights,
+    /// The options contain flags that can not be combined.
+    ConflictingOptions(CurrencyOptions),
+    /// A value is out of its allowed range.
+    OutOfRange(&'static str),
+}
+
+impl fmt::Display for CurrencyDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrencyDefinitionError::EmptyName => write!(f, "currency name is empty"),
+            CurrencyDefinitionError::MissingFractionalField(field) => {
+                write!(f, "fractional currency requires `{}`", field)
+            }
+            CurrencyDefinitionError::LengthMismatch(field) => {
+                write!(f, "`{}` needs one entry per currency", field)
+            }
+            CurrencyDefinitionError::InvalidWeights => {
+                write!(f, "weights must be positive and sum to 1")
+            }
+            CurrencyDefinitionError::ConflictingOptions(options) => {
+                write!(f, "conflicting options: {:?}", options)
+            }
+            CurrencyDefinitionError::OutOfRange(field) => write!(f, "`{}` is out of range", field),
+        }
+    }
+}
+
+impl std::error::Error for CurrencyDefinitionError {}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CurrencyEra {
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+    pub reward: Amount,
+    pub decay: u64,
+    pub halving: u64,
+    pub eraend: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CurrencyNode {
+    pub networkaddress: String,
+    pub nodeidentity: String,
+}
+
+/// The currency definition object used as argument in `definecurrency`.
+///
+/// Only `name` and `options` are required; what else is needed depends on the options.
+/// `validate` checks the definition before it is sent to the daemon.
+#[derive(Clone, Debug, Serialize)]
+pub struct CurrencyDefinitionBuilder {
+    pub name: String,
+    pub options: CurrencyOptions,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proofprotocol: Option<ProofProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notarizationprotocol: Option<NotarizationProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub startblock: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endblock: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub currencies: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conversions: Vec<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub minpreconversion: Vec<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub maxpreconversion: Vec<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub initialcontributions: Vec<f64>,
+    #[serde(
+        with = "vrsc::util::amount::serde::as_vrsc::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub initialsupply: Option<Amount>,
+    #[serde(
+        serialize_with = "serialize_preallocations",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub preallocations: Vec<(String, Amount)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prelaunchdiscount: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prelaunchcarveout: Option<f64>,
+    #[serde(
+        with = "vrsc::util::amount::serde::as_vrsc::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idregistrationfees: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idreferrallevels: Option<u8>,
+    #[serde(
+        with = "vrsc::util::amount::serde::as_vrsc::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub idimportfees: Option<Amount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gatewayconvertername: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gatewayconverterissuance: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub eras: Vec<CurrencyEra>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notaries: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minnotariesconfirm: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub nodes: Vec<CurrencyNode>,
+}
+
+fn serialize_preallocations<S: Serializer>(
+    preallocations: &[(String, Amount)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let list: Vec<HashMap<&str, f64>> = preallocations
+        .iter()
+        .map(|(id, amount)| HashMap::from([(id.as_str(), amount.as_vrsc())]))
+        .collect();
+    list.serialize(serializer)
+}
+
+impl CurrencyDefinitionBuilder {
+    pub fn new(name: &str, options: CurrencyOptions) -> Self {
+        CurrencyDefinitionBuilder {
+            name: name.to_string(),
+            options,
+            parent: None,
+            proofprotocol: None,
+            notarizationprotocol: None,
+            startblock: None,
+            endblock: None,
+            currencies: vec![],
+            weights: vec![],
+            conversions: vec![],
+            minpreconversion: vec![],
+            maxpreconversion: vec![],
+            initialcontributions: vec![],
+            initialsupply: None,
+            preallocations: vec![],
+            prelaunchdiscount: None,
+            prelaunchcarveout: None,
+            idregistrationfees: None,
+            idreferrallevels: None,
+            idimportfees: None,
+            gateway: None,
+            gatewayconvertername: None,
+            gatewayconverterissuance: None,
+            eras: vec![],
+            notaries: vec![],
+            minnotariesconfirm: None,
+            nodes: vec![],
+        }
+    }
+
+    pub fn parent(mut self, parent: &str) -> Self {
+        self.parent = Some(parent.to_string());
+        self
+    }
+
+    pub fn proofprotocol(mut self, protocol: ProofProtocol) -> Self {
+        self.proofprotocol = Some(protocol);
+        self
+    }
+
+    pub fn notarizationprotocol(mut self, protocol: NotarizationProtocol) -> Self {
+        self.notarizationprotocol = Some(protocol);
+        self
+    }
+
+    /// The blocks between which pre-conversions are possible. An end block of 0 means no end.
+    pub fn blocks(mut self, startblock: u64, endblock: u64) -> Self {
+        self.startblock = Some(startblock);
+        self.endblock = Some(endblock);
+        self
+    }
+
+    /// A reserve currency with its weight, for fractional currencies.
+    pub fn reserve(mut self, currency: &str, weight: f64) -> Self {
+        self.currencies.push(currency.to_string());
+        self.weights.push(weight);
+        self
+    }
+
+    /// A currency that can be pre-converted at a fixed price, for non-fractional currencies.
+    pub fn conversion(mut self, currency: &str, price: f64) -> Self {
+        self.currencies.push(currency.to_string());
+        self.conversions.push(price);
+        self
+    }
+
+    /// Limits on pre-conversions, one per currency.
+    pub fn preconversion_limits(mut self, min: Vec<f64>, max: Vec<f64>) -> Self {
+        self.minpreconversion = min;
+        self.maxpreconversion = max;
+        self
+    }
+
+    pub fn initialcontributions(mut self, contributions: Vec<f64>) -> Self {
+        self.initialcontributions = contributions;
+        self
+    }
+
+    pub fn initialsupply(mut self, supply: Amount) -> Self {
+        self.initialsupply = Some(supply);
+        self
+    }
+
+    pub fn preallocation(mut self, identity: &str, amount: Amount) -> Self {
+        self.preallocations.push((identity.to_string(), amount));
+        self
+    }
+
+    pub fn prelaunchdiscount(mut self, discount: f64) -> Self {
+        self.prelaunchdiscount = Some(discount);
+        self
+    }
+
+    pub fn prelaunchcarveout(mut self, carveout: f64) -> Self {
+        self.prelaunchcarveout = Some(carveout);
+        self
+    }
+
+    pub fn idregistrationfees(mut self, fees: Amount) -> Self {
+        self.idregistrationfees = Some(fees);
+        self
+    }
+
+    pub fn idreferrallevels(mut self, levels: u8) -> Self {
+        self.idreferrallevels = Some(levels);
+        self
+    }
+
+    pub fn idimportfees(mut self, fees: Amount) -> Self {
+        self.idimportfees = Some(fees);
+        self
+    }
+
+    /// The gateway this currency is bridged through.
+    pub fn gateway(mut self, gateway: &str) -> Self {
+        self.gateway = Some(gateway.to_string());
+        self
+    }
+
+    /// The name and issuance of the converter currency that is launched with a gateway or PBaaS chain.
+    pub fn gatewayconverter(mut self, name: &str, issuance: f64) -> Self {
+        self.gatewayconvertername = Some(name.to_string());
+        self.gatewayconverterissuance = Some(issuance);
+        self
+    }
+
+    pub fn era(mut self, era: CurrencyEra) -> Self {
+        self.eras.push(era);
+        self
+    }
+
+    pub fn notaries(mut self, notaries: Vec<String>, minnotariesconfirm: u16) -> Self {
+        self.notaries = notaries;
+        self.minnotariesconfirm = Some(minnotariesconfirm);
+        self
+    }
+
+    pub fn node(mut self, node: CurrencyNode) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Checks the definition for mistakes the daemon would reject it for.
+    pub fn validate(&self) -> Result<(), CurrencyDefinitionError> {
+        use CurrencyDefinitionError::*;
+
+        if self.name.trim().is_empty() {
+            return Err(EmptyName);
+        }
+
+        let options = self.options;
+        for conflict in [
+            CurrencyOptions::TOKEN | CurrencyOptions::PBAAS,
+            CurrencyOptions::GATEWAY | CurrencyOptions::PBAAS,
+        ] {
+            if options.contains(conflict) {
+                return Err(ConflictingOptions(conflict));
+            }
+        }
+        if options.contains(CurrencyOptions::GATEWAY_CONVERTER)
+            && !options.contains(CurrencyOptions::FRACTIONAL)
+        {
+            return Err(ConflictingOptions(CurrencyOptions::GATEWAY_CONVERTER));
+        }
+        if options.contains(CurrencyOptions::NFT_TOKEN) && !options.contains(CurrencyOptions::TOKEN)
+        {
+            return Err(ConflictingOptions(CurrencyOptions::NFT_TOKEN));
+        }
+
+        if options.contains(CurrencyOptions::FRACTIONAL) {
+            if self.currencies.is_empty() {
+                return Err(MissingFractionalField("currencies"));
+            }
+            if self.weights.is_empty() {
+                return Err(MissingFractionalField("weights"));
+            }
+            if self.initialsupply.is_none() {
+                return Err(MissingFractionalField("initialsupply"));
+            }
+            if self.weights.len() != self.currencies.len() {
+                return Err(LengthMismatch("weights"));
+            }
+            if self.weights.iter().any(|weight| *weight <= 0.0)
+                || (self.weights.iter().sum::<f64>() - 1.0).abs() > 1e-8
+            {
+                return Err(InvalidWeights);
+            }
+        } else if !self.conversions.is_empty() && self.conversions.len() != self.currencies.len() {
+            return Err(LengthMismatch("conversions"));
+        }
+
+        for (field, list) in [
+            ("minpreconversion", &self.minpreconversion),
+            ("maxpreconversion", &self.maxpreconversion),
+            ("initialcontributions", &self.initialcontributions),
+        ] {
+            if !list.is_empty() && list.len() != self.currencies.len() {
+                return Err(LengthMismatch(field));
+            }
+        }
+
+        for (field, value) in [
+            ("prelaunchdiscount", self.prelaunchdiscount),
+            ("prelaunchcarveout", self.prelaunchcarveout),
+        ] {
+            if matches!(value, Some(v) if !(0.0..1.0).contains(&v)) {
+                return Err(OutOfRange(field));
+            }
+        }
+
+        if matches!(self.idreferrallevels, Some(levels) if levels > 5) {
+            return Err(OutOfRange("idreferrallevels"));
+        }
+        if matches!(self.minnotariesconfirm, Some(min) if min as usize > self.notaries.len()) {
+            return Err(OutOfRange("minnotariesconfirm"));
+        }
+        if let (Some(start), Some(end)) = (self.startblock, self.endblock) {
+            if end != 0 && end <= start {
+                return Err(OutOfRange("endblock"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_fractional_definition() {
+        let basket = CurrencyDefinitionBuilder::new(
+            "basket",
+            CurrencyOptions::FRACTIONAL | CurrencyOptions::TOKEN,
+        )
+        .reserve("VRSC", 0.5)
+        .reserve("tBTC", 0.5)
+        .initialsupply(Amount::from_sat(100_000_000_000));
+        assert_eq!(basket.validate(), Ok(()));
+
+        let json = serde_json::to_value(&basket).unwrap();
+        assert_eq!(json["options"], 33);
+        assert_eq!(json["initialsupply"], 1000.0);
+        assert!(json.get("conversions").is_none());
+
+        let unbalanced = basket.clone().reserve("DAI", 0.5);
+        assert_eq!(
+            unbalanced.validate(),
+            Err(CurrencyDefinitionError::InvalidWeights)
+        );
+
+        let chain = CurrencyDefinitionBuilder::new(
+            "chain",
+            CurrencyOptions::PBAAS | CurrencyOptions::TOKEN,
+        );
+        assert!(matches!(
+            chain.validate(),
+            Err(CurrencyDefinitionError::ConflictingOptions(_))
+        ));
+    }
+
+    // A `getcurrency` result for a basket starting at block 1000, with the given flags on its
+    // last confirmed and best currency states. Built in the shape the daemon returns, not recorded.
+    fn currency(confirmed: Option<u16>, best: Option<u16>) -> GetCurrencyResult {
+        let state = |flags: u16| {
+            serde_json::json!({
+                "flags": flags,
+                "version": 1,
+                "currencyid": "iCkKJuJScy4Z6NSDK7Mt42ZAB2NEnAE1o4",
+                "initialsupply": 200000.0,
+                "emitted": 0.0,
+                "supply": 200000.0,
+                "primarycurrencyfees": 0.0,
+                "primarycurrencyconversionfees": 0.0,
+                "primarycurrencyout": 0.0,
+                "preconvertedout": 0.0
+            })
+        };
+
+        serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "options": 33,
+            "name": "basket",
+            "currencyid": "iCkKJuJScy4Z6NSDK7Mt42ZAB2NEnAE1o4",
+            "parent": "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV",
+            "systemid": "i5w5MuNik5NtLcYmNzcvaoixooEebB6MGV",
+            "notarizationprotocol": 1,
+            "proofprotocol": 1,
+            "startblock": 1000,
+            "endblock": 0,
+            "idregistrationfees": 100.0,
+            "idreferrallevels": 3,
+            "idimportfees": 0.02,
+            "currencyidhex": "",
+            "fullyqualifiedname": "basket",
+            "magicnumber": 0,
+            "definitiontxid": "1111111111111111111111111111111111111111111111111111111111111111",
+            "definitiontxout": 1,
+            "bestheight": 900,
+            "lastconfirmedcurrencystate": confirmed.map(state),
+            "bestcurrencystate": best.map(state)
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn derives_launch_state() {
+        let flags = |flags: CurrencyStateFlags| Some(flags.bits());
+        let fractional = CurrencyStateFlags::FRACTIONAL;
+        let launched = fractional
+            | CurrencyStateFlags::LAUNCH_CLEAR
+            | CurrencyStateFlags::LAUNCH_CONFIRMED
+            | CurrencyStateFlags::LAUNCH_COMPLETE;
+
+        // prelaunch, and a launched state before the start block
+        let prelaunch = currency(flags(fractional | CurrencyStateFlags::PRELAUNCH), None);
+        assert_eq!(prelaunch.launch_state(900), LaunchState::Prelaunch);
+        assert_eq!(
+            currency(flags(launched), None).launch_state(900),
+            LaunchState::Prelaunch
+        );
+
+        assert_eq!(
+            currency(flags(launched), None).launch_state(1000),
+            LaunchState::Launched
+        );
+
+        // refunding until the launch completes, failed after
+        let refunding =
+            fractional | CurrencyStateFlags::REFUNDING | CurrencyStateFlags::LAUNCH_CLEAR;
+        assert_eq!(
+            currency(flags(refunding), None).launch_state(1000),
+            LaunchState::Refunding
+        );
+        assert_eq!(
+            currency(flags(refunding | CurrencyStateFlags::LAUNCH_COMPLETE), None)
+                .launch_state(1000),
+            LaunchState::Failed
+        );
+
+        // the best state is used when nothing is confirmed yet, and the confirmed one wins
+        assert_eq!(
+            currency(None, flags(refunding)).launch_state(1000),
+            LaunchState::Refunding
+        );
+        assert_eq!(
+            currency(flags(launched), flags(refunding)).launch_state(1000),
+            LaunchState::Launched
+        );
+
+        // without any state, only the start block counts
+        assert_eq!(
+            currency(None, None).launch_state(999),
+            LaunchState::Prelaunch
+        );
+        assert_eq!(
+            currency(None, None).launch_state(1000),
+            LaunchState::Launched
+        );
+    }
+
+    #[test]
+    fn builds_time_series_per_pair() {
+        assert_eq!(CurrencyStateRange::at(10).to_string(), "10");
+        assert_eq!(CurrencyStateRange::new(10, 20).to_string(), "10,20");
+        assert_eq!(
+            CurrencyStateRange::new(10, 20).step(5).to_string(),
+            "10,20,5"
+        );
+
+        let point = |height: u64, close: f64| {
+            serde_json::json!({
+                "height": height,
+                "blocktime": 1_700_000_000 + height,
+                "currencystate": {
+                    "flags": 49,
+                    "version": 1,
+                    "currencyid": "iCkKJuJScy4Z6NSDK7Mt42ZAB2NEnAE1o4",
+                    "initialsupply": 0.0,
+                    "emitted": 0.0,
+                    "supply": 200000.0,
+                    "primarycurrencyfees": 0.0,
+                    "primarycurrencyconversionfees": 0.0,
+                    "primarycurrencyout": 0.0,
+                    "preconvertedout": 0.0
+                },
+                "conversiondata": {
+                    "volumecurrency": "VRSC",
+                    "volumethisinterval": 1.0,
+                    "volumepairs": [{
+                        "currency": "VRSC",
+                        "convertto": "tBTC",
+                        "volume": 1.0,
+                        "open": 0.0001,
+                        "high": close,
+                        "low": 0.0001,
+                        "close": close
+                    }]
+                }
+            })
+        };
+        let results: Vec<GetCurrencyStateResult> = serde_json::from_value(serde_json::json!([
+            point(20, 0.0003),
+            point(10, 0.0002),
+            { "totalvolume": 2.0 }
+        ]))
+        .unwrap();
+
+        let series = time_series(&results);
+        let candles = &series[&(String::from("VRSC"), String::from("tBTC"))];
+        assert_eq!(series.len(), 1);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].height, 10);
+        assert_eq!(candles[1].close, Amount::from_sat(30_000));
+    }
+}
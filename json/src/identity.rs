@@ -7,7 +7,7 @@ use bitcoin::{
 use serde::de::{Deserialize, Deserializer};
 use serde_with::serde_as;
 use serde_with::NoneAsEmptyString;
-use vrsc::Address;
+use vrsc::{Address, Amount};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IdentityHistory {
@@ -113,7 +113,8 @@ where
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MarketplaceOffer {
     pub identityid: Address,
-    pub price: f64,
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+    pub price: Amount,
     pub offer: Offer,
 }
 
@@ -164,3 +165,229 @@ pub struct BoundData {
     pub uint256: hash256,
     pub indexnum: u32,
 }
+
+/// An identity definition as accepted by the daemon in `registeridentity`, `updateidentity`
+/// and as the identity side of a marketplace offer.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct IdentityDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primaryaddresses: Option<Vec<Address>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimumsignatures: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocationauthority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recoveryauthority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privateaddress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contentmap: Option<serde_json::Value>,
+}
+
+impl From<&IdentityPrimary> for IdentityDefinition {
+    fn from(identity: &IdentityPrimary) -> Self {
+        IdentityDefinition {
+            name: identity.name.clone(),
+            parent: Some(identity.parent.to_string()),
+            primaryaddresses: Some(identity.primaryaddresses.clone()),
+            minimumsignatures: Some(identity.minimumsignatures),
+            revocationauthority: Some(identity.revocationauthority.to_string()),
+            recoveryauthority: Some(identity.recoveryauthority.to_string()),
+            privateaddress: identity.privateaddress.clone(),
+            contentmap: Some(identity.contentmap.clone()),
+        }
+    }
+}
+
+/// The `offer` side of `makeoffer`: what the maker puts up.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum OfferSend {
+    Currency {
+        currency: String,
+        #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+        amount: Amount,
+    },
+    Identity {
+        identity: String,
+    },
+}
+
+impl OfferSend {
+    /// Turns one side of an existing offer (as returned by `getoffers`) into something that can be offered.
+    /// Returns None if the variant holds more than one currency, as `makeoffer` only accepts one.
+    pub fn from_variant(variant: &OfferVariant) -> Option<Self> {
+        match variant {
+            OfferVariant::CurrencyOffer(map) if map.len() == 1 => {
+                let (currency, amount) = map.iter().next()?;
+                Some(OfferSend::Currency {
+                    currency: currency.clone(),
+                    amount: Amount::from_vrsc(*amount).ok()?,
+                })
+            }
+            OfferVariant::CurrencyOffer(_) => None,
+            OfferVariant::IdentityOffer(identity) => Some(OfferSend::Identity {
+                identity: identity.identityaddress.to_string(),
+            }),
+        }
+    }
+}
+
+/// The `for` side of `makeoffer` and the `accept` side of `takeoffer`: what is received in return.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum OfferReceive {
+    Currency {
+        address: String,
+        currency: String,
+        #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+        amount: Amount,
+    },
+    Identity(IdentityDefinition),
+}
+
+impl OfferReceive {
+    /// Turns one side of an existing offer (as returned by `getoffers`) into something to receive.
+    /// Currency is sent to `address`; an identity keeps its current definition.
+    /// Returns None if the variant holds more than one currency.
+    pub fn from_variant(variant: &OfferVariant, address: &str) -> Option<Self> {
+        match variant {
+            OfferVariant::CurrencyOffer(map) if map.len() == 1 => {
+                let (currency, amount) = map.iter().next()?;
+                Some(OfferReceive::Currency {
+                    address: address.to_string(),
+                    currency: currency.clone(),
+                    amount: Amount::from_vrsc(*amount).ok()?,
+                })
+            }
+            OfferVariant::CurrencyOffer(_) => None,
+            OfferVariant::IdentityOffer(identity) => {
+                Some(OfferReceive::Identity(IdentityDefinition::from(identity)))
+            }
+        }
+    }
+}
+
+/// The offer object used as argument in `makeoffer`.
+#[derive(Clone, Debug, Serialize)]
+pub struct MakeOfferRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changeaddress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiryheight: Option<u64>,
+    pub offer: OfferSend,
+    #[serde(rename = "for")]
+    pub for_: OfferReceive,
+}
+
+impl MakeOfferRequest {
+    pub fn new(offer: OfferSend, for_: OfferReceive) -> Self {
+        MakeOfferRequest {
+            changeaddress: None,
+            expiryheight: None,
+            offer,
+            for_,
+        }
+    }
+
+    pub fn changeaddress(mut self, changeaddress: &str) -> Self {
+        self.changeaddress = Some(changeaddress.to_string());
+        self
+    }
+
+    pub fn expiryheight(mut self, expiryheight: u64) -> Self {
+        self.expiryheight = Some(expiryheight);
+        self
+    }
+}
+
+/// `txid` is set when the offer was posted, `hex` when `returntx` was requested and the
+/// (partial) transaction is returned instead.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MakeOfferResult {
+    pub txid: Option<Txid>,
+    pub hex: Option<String>,
+}
+
+/// Refers to the offer to take: either by the txid of a posted offer, or by the hex of an
+/// offer transaction that was passed around off-chain.
+#[derive(Clone, Debug, Serialize)]
+pub enum TakeOfferSource {
+    #[serde(rename = "txid")]
+    Txid(Txid),
+    #[serde(rename = "tx")]
+    Tx(String),
+}
+
+/// The `deliver` side of `takeoffer`: what the taker gives in return.
+#[derive(Clone, Debug, Serialize)]
+#[serde(untagged)]
+pub enum OfferDeliver {
+    Currency {
+        currency: String,
+        #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+        amount: Amount,
+    },
+    Identity(String),
+}
+
+impl OfferDeliver {
+    /// Returns None if the variant holds more than one currency.
+    pub fn from_variant(variant: &OfferVariant) -> Option<Self> {
+        match OfferSend::from_variant(variant)? {
+            OfferSend::Currency { currency, amount } => {
+                Some(OfferDeliver::Currency { currency, amount })
+            }
+            OfferSend::Identity { identity } => Some(OfferDeliver::Identity(identity)),
+        }
+    }
+}
+
+/// The offer object used as argument in `takeoffer`.
+#[derive(Clone, Debug, Serialize)]
+pub struct TakeOfferRequest {
+    #[serde(flatten)]
+    pub source: TakeOfferSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changeaddress: Option<String>,
+    pub deliver: OfferDeliver,
+    pub accept: OfferReceive,
+}
+
+impl TakeOfferRequest {
+    pub fn new(source: TakeOfferSource, deliver: OfferDeliver, accept: OfferReceive) -> Self {
+        TakeOfferRequest {
+            source,
+            changeaddress: None,
+            deliver,
+            accept,
+        }
+    }
+
+    /// Takes an offer as found by `getoffers` or `listopenoffers`: delivers what the maker asked for
+    /// and accepts what was offered, sending any currency to `address`.
+    pub fn from_offer(offer: &Offer, address: &str) -> Option<Self> {
+        Some(TakeOfferRequest::new(
+            TakeOfferSource::Txid(offer.txid),
+            OfferDeliver::from_variant(&offer.accept)?,
+            OfferReceive::from_variant(&offer.offer, address)?,
+        ))
+    }
+
+    pub fn changeaddress(mut self, changeaddress: &str) -> Self {
+        self.changeaddress = Some(changeaddress.to_string());
+        self
+    }
+}
+
+/// An offer made from this wallet, as returned by `listopenoffers`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OpenOffer {
+    #[serde(default)]
+    pub expired: bool,
+    #[serde(flatten)]
+    pub offer: Offer,
+}
@@ -9,11 +9,18 @@ extern crate serde_json;
 pub extern crate bitcoin;
 pub extern crate vrsc;
 
+mod blake2b;
+pub mod conversion;
+pub mod currency;
 pub mod identity;
+pub mod merkle;
+pub mod raw;
+pub mod trust;
 
 use crate::vrsc::{Address, Amount, PrivateKey, PublicKey, SignedAmount};
 
 use bitcoin::{BlockHash, Script, Txid};
+use currency::{CurrencyOptions, CurrencyStateFlags, NotarizationProtocol, ProofProtocol};
 use identity::{IdentityPrimary, IdentityReservation};
 use serde::*;
 use serde_json::Value;
@@ -53,18 +60,23 @@ pub struct ListCurrenciesResult(pub Vec<Currency>);
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Currency {
     pub currencydefinition: CurrencyDefinition,
+    pub bestheight: Option<u64>,
+    pub besttxid: Option<Txid>,
+    pub besttxout: Option<u32>,
+    pub bestcurrencystate: Option<CurrencyState>,
+    pub lastnotarization: Option<CurrencyConverterNotarization>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyDefinition {
     pub version: u8,
-    pub options: u16,
+    pub options: CurrencyOptions,
     pub name: String,
     pub currencyid: Address,
     pub parent: Option<Address>,
     pub systemid: Address,
-    pub notarizationprotocol: u32,
-    pub proofprotocol: u32,
+    pub notarizationprotocol: NotarizationProtocol,
+    pub proofprotocol: ProofProtocol,
     pub launchsystemid: Option<Address>,
     pub currencyidhex: Option<String>,
     pub fullyqualifiedname: String,
@@ -76,13 +88,13 @@ pub struct PreAllocation(pub HashMap<String, f64>);
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GetCurrencyResult {
     pub version: u16,
-    pub options: u16,
+    pub options: CurrencyOptions,
     pub name: String,
     pub currencyid: Address,
     pub parent: Option<String>,
     pub systemid: Address,
-    pub notarizationprotocol: u8,
-    pub proofprotocol: u8,
+    pub notarizationprotocol: NotarizationProtocol,
+    pub proofprotocol: ProofProtocol,
     pub launchsystemid: Option<Address>,
     pub startblock: u64,
     pub endblock: u64,
@@ -112,6 +124,13 @@ pub struct GetCurrencyResult {
     pub lastconfirmedcurrencystate: Option<CurrencyState>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DefineCurrencyResult {
+    pub txid: Txid,
+    pub tx: Value,
+    pub hex: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyNames(pub HashMap<Address, String>);
 
@@ -132,7 +151,7 @@ pub struct GetCurrencyStateResultInner {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyState {
-    pub flags: u16,
+    pub flags: CurrencyStateFlags,
     pub version: u16,
     pub currencyid: Address,
     pub reservecurrencies: Option<Vec<ReserveCurrency>>,
@@ -204,6 +223,38 @@ pub struct ReserveCurrency {
     pub priceinreserve: Amount,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EstimateConversionResult {
+    pub inputcurrencyid: Option<Address>,
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc::opt", default)]
+    pub netinputamount: Option<Amount>,
+    pub outputcurrencyid: Option<Address>,
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+    pub estimatedcurrencyout: Amount,
+    pub estimatedcurrencystate: CurrencyState,
+}
+
+impl EstimateConversionResult {
+    /// The state of one of the currencies in the basket after the conversion.
+    pub fn currency_state(&self, currencyid: &Address) -> Option<&CurrencyStateCurrency> {
+        self.estimatedcurrencystate
+            .currencies
+            .as_ref()?
+            .get(currencyid)
+    }
+
+    /// The conversion fees per currency in the estimated state, leaving out currencies without fees.
+    pub fn conversion_fees(&self) -> HashMap<Address, Amount> {
+        self.estimatedcurrencystate
+            .currencies
+            .iter()
+            .flatten()
+            .filter(|(_, state)| state.conversionfees > Amount::ZERO)
+            .map(|(currencyid, state)| (currencyid.clone(), state.conversionfees))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyBalanceResult(pub HashMap<String, f64>);
 
@@ -220,13 +271,13 @@ pub struct GetCurrencyConvertersResult {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyConverterCurrency {
     pub version: u16,
-    pub options: u16,
+    pub options: CurrencyOptions,
     pub name: String,
     pub currencyid: Address,
     pub parent: Address,
     pub systemid: Address,
-    pub notarizationprotocol: u16,
-    pub proofprotocol: u16,
+    pub notarizationprotocol: NotarizationProtocol,
+    pub proofprotocol: ProofProtocol,
     pub launchsystemid: Address,
     pub startblock: u64,
     pub endblock: u64,
@@ -245,8 +296,8 @@ pub struct CurrencyConverterCurrency {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CurrencyConverterOutput {
-    txid: Txid,
-    voutnum: u64,
+    pub txid: Txid,
+    pub voutnum: u64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -264,7 +315,7 @@ pub struct CurrencyConverterNotarization {
     pub prevnotarizationout: u64,
     pub prevheight: u64,
     pub hashprevcrossnotarization: String,
-    pub currencystates: Vec<String>,
+    pub currencystates: Vec<HashMap<Address, CurrencyState>>,
     pub proofroots: Vec<ProofRoot>,
     pub nodes: Vec<String>,
 }
@@ -295,13 +346,41 @@ pub enum NotarizationProposer {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ZOperationStatusResult {
     pub id: String,
-    pub status: String,
+    pub status: ZOperationStatus,
     pub creation_time: u64,
     pub result: Option<ZOperationStatusResultTxid>,
     pub error: Option<ZOperationStatusResultError>,
     pub execution_secs: Option<f64>,
     pub method: String,
-    pub params: Vec<Option<ZOperationStatusResultParam>>,
+    pub params: ZOperationStatusResultParams,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ZOperationStatus {
+    Queued,
+    Executing,
+    Success,
+    Failed,
+    Cancelled,
+}
+
+impl ZOperationStatus {
+    /// Whether the operation has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            ZOperationStatus::Success | ZOperationStatus::Failed | ZOperationStatus::Cancelled
+        )
+    }
+}
+
+// sendcurrency lists its outputs, the z_ calls each return their own object of arguments.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ZOperationStatusResultParams {
+    Outputs(Vec<Option<ZOperationStatusResultParam>>),
+    Other(Value),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -339,10 +418,43 @@ pub struct SignRawTransactionResultError {
     pub error: String,
 }
 
+// Used for signrawtransaction argument.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct SignRawTransactionInput {
+    pub txid: Txid,
+    pub vout: u32,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: Script,
+    #[serde(rename = "redeemScript", skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<Script>,
+    #[serde(
+        with = "vrsc::util::amount::serde::as_vrsc::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub amount: Option<Amount>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SigHashType {
+    #[serde(rename = "ALL")]
+    All,
+    #[serde(rename = "NONE")]
+    None,
+    #[serde(rename = "SINGLE")]
+    Single,
+    #[serde(rename = "ALL|ANYONECANPAY")]
+    AllAnyoneCanPay,
+    #[serde(rename = "NONE|ANYONECANPAY")]
+    NoneAnyoneCanPay,
+    #[serde(rename = "SINGLE|ANYONECANPAY")]
+    SingleAnyoneCanPay,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SendCurrencyResult {
-    txid: Option<Txid>,
-    hextx: Option<String>,
+    pub txid: Option<Txid>,
+    pub hextx: Option<String>,
+    pub outputtotals: Option<HashMap<Address, f64>>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -392,6 +504,45 @@ pub struct AddressUtxos {
     pub script: String,
     pub satoshis: u64,
     pub height: u64,
+    /// The amounts of other currencies held in the output, by currency id.
+    #[serde(with = "amount_map", default)]
+    pub currencyvalues: HashMap<Address, Amount>,
+}
+
+/// `getaddressutxos` with `chainInfo`, which also reports the tip the outputs were read at.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AddressUtxosWithChainInfo {
+    pub utxos: Vec<AddressUtxos>,
+    pub hash: BlockHash,
+    pub height: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AddressBalance {
+    #[serde(with = "vrsc::util::amount::serde::as_sat")]
+    pub balance: Amount,
+    #[serde(with = "vrsc::util::amount::serde::as_sat")]
+    pub received: Amount,
+    /// The balance of each currency held by the addresses, by currency id.
+    #[serde(with = "amount_map", default)]
+    pub currencybalance: HashMap<Address, Amount>,
+    #[serde(with = "amount_map", default)]
+    pub currencyreceived: HashMap<Address, Amount>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AddressMempoolDelta {
+    pub address: Address,
+    pub txid: Txid,
+    pub index: u32,
+    #[serde(with = "vrsc::util::amount::serde::as_sat")]
+    pub satoshis: SignedAmount,
+    pub timestamp: u64,
+    /// The output spent, when the delta is spending.
+    pub prevtxid: Option<Txid>,
+    pub prevout: Option<u32>,
+    #[serde(with = "amount_map", default)]
+    pub currencyvalues: HashMap<Address, Amount>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -498,7 +649,7 @@ pub enum ValidationType {
     Work,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct ProofRoot {
     pub version: u32,
     #[serde(rename = "type")]
@@ -511,6 +662,163 @@ pub struct ProofRoot {
     pub gasprice: Option<f64>,
 }
 
+impl ProofRoot {
+    /// Whether this proof root, as found in a notarization, commits to the given block of its
+    /// system.
+    pub fn confirms(&self, block: &Block) -> bool {
+        self.systemid == block.proofroot.systemid
+            && self.height == block.height
+            && self.blockhash == block.hash
+            && self.stateroot == block.proofroot.stateroot
+    }
+}
+
+impl CurrencyConverterNotarization {
+    pub fn proof_root(&self, systemid: &Address) -> Option<&ProofRoot> {
+        self.proofroots
+            .iter()
+            .find(|proofroot| &proofroot.systemid == systemid)
+    }
+
+    pub fn currency_state(&self, currencyid: &Address) -> Option<&CurrencyState> {
+        if &self.currencyid == currencyid {
+            return Some(&self.currencystate);
+        }
+
+        self.currencystates
+            .iter()
+            .find_map(|states| states.get(currencyid))
+    }
+}
+
+/// The reserves held for a currency, by reserve currency.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReserveDeposits(#[serde(with = "amount_map")] pub HashMap<Address, Amount>);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetNotarizationDataResult {
+    pub version: u32,
+    #[serde(default)]
+    pub notarizations: Vec<NotarizationDataEntry>,
+    /// Chains of indexes into `notarizations`, one per fork.
+    #[serde(default)]
+    pub forks: Vec<Vec<usize>>,
+    pub lastconfirmedheight: Option<u64>,
+    /// Index of the last confirmed notarization.
+    pub lastconfirmed: Option<i64>,
+    /// Index into `forks` of the best chain.
+    pub bestchain: Option<usize>,
+}
+
+impl GetNotarizationDataResult {
+    pub fn last_confirmed(&self) -> Option<&NotarizationDataEntry> {
+        self.notarizations
+            .get(usize::try_from(self.lastconfirmed?).ok()?)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NotarizationDataEntry {
+    pub index: Option<usize>,
+    pub txid: Txid,
+    pub vout: u32,
+    pub notarization: CurrencyConverterNotarization,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetBestProofRootResult {
+    pub bestindex: Option<i64>,
+    #[serde(default)]
+    pub validindexes: Vec<usize>,
+    pub latestproofroot: Option<ProofRoot>,
+    pub laststableproofroot: Option<ProofRoot>,
+    pub lastconfirmedproofroot: Option<ProofRoot>,
+    #[serde(default)]
+    pub currencystates: Vec<HashMap<Address, CurrencyState>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetLaunchInfoResult {
+    pub currencydefinition: GetCurrencyResult,
+    pub txid: Txid,
+    pub voutnum: u32,
+    pub transactionproof: Option<Value>,
+    pub launchnotarization: CurrencyConverterNotarization,
+    pub notarynotarization: Option<CurrencyConverterNotarization>,
+}
+
+/// Evidence for or against a notarization, as stored on chain by notaries.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NotaryEvidence {
+    pub version: u32,
+    #[serde(rename = "type")]
+    pub ty: u32,
+    pub systemid: Address,
+    /// The notarization output the evidence is about.
+    pub output: CurrencyConverterOutput,
+    pub state: u32,
+    /// The proofs themselves, such as partial transactions, headers and MMR proofs. Their shape
+    /// depends on the kind of each chain object, so they are kept as the daemon serializes them.
+    pub evidence: Value,
+}
+
+/// A request to `getnotarizationproofs`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum NotarizationProofRequest {
+    /// Proves that the block at `proveheight` is on the chain that was notarized at `atheight`.
+    #[serde(rename = "vrsc::evidence.skipchallenge")]
+    SkipChallenge {
+        evidence: NotaryEvidence,
+        entropyhash: String,
+        proveheight: u64,
+        atheight: u64,
+    },
+    /// Proves a notarization from a prior one, answering the given challenge roots.
+    #[serde(rename = "vrsc::evidence.primaryproof")]
+    PrimaryProof {
+        #[serde(flatten)]
+        prior: PriorNotarization,
+        #[serde(default)]
+        challengeroots: Vec<ChallengeRoot>,
+        evidence: NotaryEvidence,
+        entropyhash: String,
+        #[serde(flatten)]
+        confirm: ConfirmNotarization,
+        fromheight: u64,
+        toheight: u64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PriorNotarization {
+    #[serde(rename = "priornotarizationref")]
+    Ref(CurrencyConverterOutput),
+    #[serde(rename = "priorroot")]
+    Root(Box<ProofRoot>),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ConfirmNotarization {
+    #[serde(rename = "confirmnotarization")]
+    Notarization(Box<CurrencyConverterNotarization>),
+    #[serde(rename = "confirmroot")]
+    Root(Box<ProofRoot>),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ChallengeRoot {
+    /// The chain object that identifies what is challenged.
+    pub indexkey: Value,
+    pub proofroot: ProofRoot,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetNotarizationProofsResult {
+    /// One entry per request, in the same order.
+    pub evidence: Vec<NotaryEvidence>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ValuePool {
@@ -733,6 +1041,33 @@ pub struct GetRawTransactionResultVerbose {
     pub blocktime: Option<u64>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DecodeRawTransactionResult {
+    pub txid: Txid,
+    pub overwintered: bool,
+    pub version: u32,
+    pub versiongroupid: Option<String>,
+    pub locktime: u64,
+    pub expiryheight: Option<u64>,
+    pub vin: Vec<TransactionVin>,
+    pub vout: Vec<TransactionVout>,
+    #[serde(default)]
+    pub vjoinsplit: Vec<GetRawTransactionVJoinSplit>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DecodeScriptResult {
+    pub asm: String,
+    pub hex: Option<String>,
+    #[serde(rename = "type")]
+    pub r#type: String,
+    #[serde(rename = "reqSigs")]
+    pub req_sigs: Option<u32>,
+    pub addresses: Option<Vec<Address>>,
+    /// The P2SH address that wraps this script.
+    pub p2sh: Option<Address>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TransactionVin {
     pub txid: Option<bitcoin::Txid>,
@@ -831,6 +1166,55 @@ pub struct GetRawTransactionScriptPubKeyReserveImport {
     currencyvalues: Value,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrossChainExport {
+    pub version: u8,
+    #[serde(alias = "exportflags")]
+    pub flags: Option<i32>,
+    pub sourcesystemid: Address,
+    pub hashtransfers: String,
+    pub destinationsystemid: Address,
+    pub destinationcurrencyid: Address,
+    pub sourceheightstart: u64,
+    pub sourceheightend: u64,
+    pub numinputs: u32,
+    pub firstinput: Option<i32>,
+    pub totalamounts: Option<HashMap<Address, f64>>,
+    pub totalfees: Option<HashMap<Address, f64>>,
+    pub totalburned: Option<HashMap<Address, f64>>,
+    pub rewardaddress: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetExportsResult {
+    pub height: u64,
+    pub txid: Txid,
+    pub txoutnum: u32,
+    pub exportinfo: CrossChainExport,
+    pub partialtransactionproof: Option<String>,
+    #[serde(default)]
+    pub transfers: Vec<GetRawTransactionScriptPubKeyReserveTransfer>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetImportsResult {
+    pub importheight: u64,
+    pub importtxid: Txid,
+    pub importvout: Option<u32>,
+    pub import: GetRawTransactionScriptPubKeyCrossChainImport,
+    pub importnotarization: Option<CurrencyConverterNotarization>,
+    #[serde(default)]
+    pub transfers: Vec<GetRawTransactionScriptPubKeyReserveTransfer>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GetLastImportFromResult {
+    pub lastimport: Option<GetRawTransactionScriptPubKeyCrossChainImport>,
+    pub lastimportutxo: Option<Value>,
+    pub lastconfirmednotarization: Option<CurrencyConverterNotarization>,
+    pub lastconfirmedutxo: Option<Value>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GetRawTransactionVJoinSplit {
     #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
@@ -990,6 +1374,7 @@ pub struct RawMempoolTransactionInfo {
 pub struct SpentInfoResult {
     pub txid: bitcoin::Txid,
     pub index: u32,
+    pub height: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -1068,6 +1453,16 @@ pub struct CreateRawTransactionInput {
     pub sequence: Option<u32>,
 }
 
+// Used for fundrawtransaction argument.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FundRawTransactionResult {
+    pub hex: String,
+    #[serde(with = "vrsc::util::amount::serde::as_vrsc")]
+    pub fee: Amount,
+    /// The position of the change output, or -1 if no change was added.
+    pub changepos: i32,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Snapshot {
     pub start_time: u64,
@@ -1092,6 +1487,37 @@ pub struct SnapshotAddress {
     pub amount: f64,
 }
 
+/// (De)serializes a map of amounts in VRSC by currency, as the daemon returns reserve balances.
+pub mod amount_map {
+    use crate::vrsc::{Address, Amount};
+    use serde::{de, ser::SerializeMap, Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<Address, Amount>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut ser = serializer.serialize_map(Some(map.len()))?;
+        for (currency, amount) in map {
+            ser.serialize_entry(currency, &amount.as_vrsc())?;
+        }
+        ser.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Address, Amount>, D::Error> {
+        HashMap::<Address, f64>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(currency, amount)| {
+                Amount::from_vrsc(amount)
+                    .map(|amount| (currency, amount))
+                    .map_err(de::Error::custom)
+            })
+            .collect()
+    }
+}
+
 fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     T: FromStr,
@@ -0,0 +1,220 @@
+//! Verifying the proofs returned by `gettxoutproof` without asking the daemon.
+//!
+//! A proof is a serialized merkle block: the header of the block, followed by a partial merkle
+//! tree that commits to the proven txids. The tree has the same format as in Bitcoin.
+
+use bitcoin::consensus::encode::{self, Decodable, Encodable};
+use bitcoin::util::merkleblock::{MerkleBlockError, PartialMerkleTree};
+use bitcoin::{TxMerkleNode, Txid};
+use std::{fmt, io, str::FromStr};
+
+use crate::raw::{self, BlockHeader};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleBlock {
+    pub header: BlockHeader,
+    pub txn: PartialMerkleTree,
+}
+
+#[derive(Debug)]
+pub enum MerkleProofError {
+    Decode(encode::Error),
+    /// The trusted merkle root could not be parsed.
+    InvalidMerkleRoot(String),
+    /// The header in the proof is for another block than the trusted merkle root.
+    MerkleRootMismatch,
+    /// The partial merkle tree does not hash to the merkle root in the header.
+    InvalidTree(MerkleBlockError),
+}
+
+impl fmt::Display for MerkleProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleProofError::Decode(e) => write!(f, "invalid proof: {}", e),
+            MerkleProofError::InvalidMerkleRoot(root) => {
+                write!(f, "invalid merkle root: {}", root)
+            }
+            MerkleProofError::MerkleRootMismatch => {
+                write!(f, "proof is for a different merkle root")
+            }
+            MerkleProofError::InvalidTree(e) => write!(f, "invalid merkle tree: {:?}", e),
+        }
+    }
+}
+
+impl std::error::Error for MerkleProofError {}
+
+impl MerkleBlock {
+    pub fn from_hex(proof: &str) -> Result<Self, MerkleProofError> {
+        raw::deserialize_hex(proof).map_err(MerkleProofError::Decode)
+    }
+
+    /// Checks that the proof commits to `merkleroot` and returns the txids it proves, in block
+    /// order, with their positions in the block.
+    pub fn verify(&self, merkleroot: &TxMerkleNode) -> Result<Vec<(u32, Txid)>, MerkleProofError> {
+        if &self.header.merkle_root != merkleroot {
+            return Err(MerkleProofError::MerkleRootMismatch);
+        }
+
+        let mut matches = vec![];
+        let mut indexes = vec![];
+        let root = self
+            .txn
+            .extract_matches(&mut matches, &mut indexes)
+            .map_err(MerkleProofError::InvalidTree)?;
+        if &root != merkleroot {
+            return Err(MerkleProofError::InvalidTree(
+                MerkleBlockError::MerkleRootMismatch,
+            ));
+        }
+
+        Ok(indexes.into_iter().zip(matches).collect())
+    }
+}
+
+/// Verifies a `gettxoutproof` proof against the merkle root of a header that is already trusted,
+/// and returns the txids it proves.
+pub fn verify_txout_proof(
+    proof: &str,
+    header: &crate::BlockHeader,
+) -> Result<Vec<Txid>, MerkleProofError> {
+    let merkleroot = TxMerkleNode::from_str(&header.merkleroot)
+        .map_err(|_| MerkleProofError::InvalidMerkleRoot(header.merkleroot.clone()))?;
+
+    Ok(MerkleBlock::from_hex(proof)?
+        .verify(&merkleroot)?
+        .into_iter()
+        .map(|(_, txid)| txid)
+        .collect())
+}
+
+impl Decodable for MerkleBlock {
+    fn consensus_decode_from_finite_reader<R: io::Read + ?Sized>(
+        r: &mut R,
+    ) -> Result<Self, encode::Error> {
+        Ok(MerkleBlock {
+            header: Decodable::consensus_decode_from_finite_reader(r)?,
+            txn: Decodable::consensus_decode_from_finite_reader(r)?,
+        })
+    }
+}
+
+impl Encodable for MerkleBlock {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        Ok(self.header.consensus_encode(w)? + self.txn.consensus_encode(w)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+    use bitcoin::BlockHash;
+
+    fn header(merkle_root: TxMerkleNode) -> BlockHeader {
+        BlockHeader {
+            version: 65540,
+            prev_blockhash: BlockHash::from_inner([1; 32]),
+            merkle_root,
+            final_sapling_root: [2; 32],
+            time: 1_700_000_000,
+            bits: 0x1b02_8f4c,
+            nonce: [3; 32],
+            solution: vec![4; 1344],
+        }
+    }
+
+    // The header as `getblockheader` returns it, with only the fields the proof is checked against.
+    fn trusted(merkleroot: &TxMerkleNode) -> crate::BlockHeader {
+        crate::BlockHeader {
+            hash: BlockHash::from_inner([5; 32]),
+            confirmations: 1,
+            height: 1,
+            version: 65540,
+            merkleroot: merkleroot.to_string(),
+            time: 1_700_000_000,
+            nonce: String::new(),
+            solution: String::new(),
+            bits: String::from("1b028f4c"),
+            difficulty: 1.0,
+            chainwork: String::new(),
+            segid: -1,
+            previousblockhash: None,
+            nextblockhash: None,
+        }
+    }
+
+    // A proof in the `gettxoutproof` format for a block of five transactions, proving the second
+    // and the fourth. It is built here rather than recorded from a daemon.
+    fn proof() -> (MerkleBlock, Vec<Txid>) {
+        let txids: Vec<Txid> = (1..=5u8).map(|i| Txid::hash(&[i; 32])).collect();
+        let txn = PartialMerkleTree::from_txids(&txids, &[false, true, false, true, false]);
+        let mut matches = vec![];
+        let mut indexes = vec![];
+        let merkle_root = txn.extract_matches(&mut matches, &mut indexes).unwrap();
+
+        let block = MerkleBlock {
+            header: header(merkle_root),
+            txn,
+        };
+
+        (block, txids)
+    }
+
+    // The tree has the Bitcoin format, so the tree of a proof recorded from bitcoind with
+    // `gettxoutproof '["5a4ebf66822b0b2d56bd9dc64ece0bc38ee7844a23ff1d7320a88c5fdb2ad3e2"]'`
+    // checks the tree against real data, behind a header in the Verus format.
+    #[test]
+    fn verifies_recorded_tree() {
+        let tree = "0200000002252bf9d75c4f481ebb6278d708257d1f12beb6dd30301d26c623f789b2ba6fc0e2d32adb5f8ca820731dff234a84e78ec30bce4ec69dbd562d0b2b8266bf4e5a0105";
+        let merkle_root = TxMerkleNode::from_str(
+            "8fb300e3fdb6f30a4c67233b997f99fdd518b968b9a3fd65857bfe78b2600719",
+        )
+        .unwrap();
+        let hex = format!("{}{}", encode::serialize_hex(&header(merkle_root)), tree);
+
+        assert_eq!(
+            verify_txout_proof(&hex, &trusted(&merkle_root)).unwrap(),
+            vec![Txid::from_str(
+                "5a4ebf66822b0b2d56bd9dc64ece0bc38ee7844a23ff1d7320a88c5fdb2ad3e2"
+            )
+            .unwrap()]
+        );
+        assert_eq!(
+            encode::serialize_hex(&MerkleBlock::from_hex(&hex).unwrap()),
+            hex
+        );
+    }
+
+    #[test]
+    fn verifies_proof_against_trusted_header() {
+        let (block, txids) = proof();
+        let hex = encode::serialize_hex(&block);
+
+        assert_eq!(
+            verify_txout_proof(&hex, &trusted(&block.header.merkle_root)).unwrap(),
+            vec![txids[1], txids[3]]
+        );
+        assert_eq!(
+            MerkleBlock::from_hex(&hex)
+                .unwrap()
+                .verify(&block.header.merkle_root)
+                .unwrap(),
+            vec![(1, txids[1]), (3, txids[3])]
+        );
+
+        let other = TxMerkleNode::from_inner([6; 32]);
+        assert!(matches!(
+            block.verify(&other),
+            Err(MerkleProofError::MerkleRootMismatch)
+        ));
+
+        // a header that claims the trusted root, but a tree that doesn't hash to it
+        let mut forged = block.clone();
+        forged.txn = PartialMerkleTree::from_txids(&txids[..4], &[false, true, false, true]);
+        assert!(matches!(
+            forged.verify(&block.header.merkle_root),
+            Err(MerkleProofError::InvalidTree(_))
+        ));
+    }
+}
@@ -0,0 +1,637 @@
+//! Consensus (de)serialization of Verus transactions and blocks, as returned by
+//! `getrawtransaction` and `getblock` with verbosity 0.
+//!
+//! Transactions follow the Zcash formats: pre-Overwinter transactions, Overwinter (v3) and Sapling
+//! (v4). Inputs and transparent outputs are the same as in Bitcoin and reuse its types. Block
+//! headers carry the final Sapling root, a 256 bit nonce and the Equihash solution, which for
+//! VerusHash blocks also holds the PBaaS header data.
+//!
+//! The block hash is not computed, as that needs VerusHash.
+
+use bitcoin::consensus::encode::{self, Decodable, Encodable, VarInt};
+use bitcoin::hashes::Hash;
+use bitcoin::{BlockHash, Script, TxIn, TxMerkleNode, TxOut, Txid};
+use std::io;
+
+use crate::blake2b::blake2b_256;
+
+pub const OVERWINTER_VERSION_GROUP_ID: u32 = 0x03C4_8270;
+pub const SAPLING_VERSION_GROUP_ID: u32 = 0x892F_2085;
+
+const OVERWINTERED_FLAG: u32 = 1 << 31;
+const GROTH_PROOF_SIZE: usize = 192;
+const PHGR_PROOF_SIZE: usize = 296;
+const NOTE_CIPHERTEXT_SIZE: usize = 601;
+const ENC_CIPHERTEXT_SIZE: usize = 580;
+const OUT_CIPHERTEXT_SIZE: usize = 80;
+
+pub const SIGHASH_ALL: u32 = 1;
+pub const SIGHASH_NONE: u32 = 2;
+pub const SIGHASH_SINGLE: u32 = 3;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpendDescription {
+    pub cv: [u8; 32],
+    pub anchor: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub rk: [u8; 32],
+    pub zkproof: Vec<u8>,
+    pub spend_auth_sig: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputDescription {
+    pub cv: [u8; 32],
+    pub cmu: [u8; 32],
+    pub ephemeral_key: [u8; 32],
+    pub enc_ciphertext: Vec<u8>,
+    pub out_ciphertext: Vec<u8>,
+    pub zkproof: Vec<u8>,
+}
+
+/// A Sprout joinsplit. Sapling transactions use Groth proofs, older ones PHGR proofs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JoinSplit {
+    pub vpub_old: u64,
+    pub vpub_new: u64,
+    pub anchor: [u8; 32],
+    pub nullifiers: [[u8; 32]; 2],
+    pub commitments: [[u8; 32]; 2],
+    pub ephemeral_key: [u8; 32],
+    pub random_seed: [u8; 32],
+    pub macs: [[u8; 32]; 2],
+    pub proof: Vec<u8>,
+    pub ciphertexts: [Vec<u8>; 2],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub overwintered: bool,
+    pub version: u32,
+    /// Zero for transactions that are not overwintered.
+    pub version_group_id: u32,
+    pub input: Vec<TxIn>,
+    pub output: Vec<TxOut>,
+    pub lock_time: u32,
+    /// Zero for transactions that are not overwintered.
+    pub expiry_height: u32,
+    pub value_balance: i64,
+    pub shielded_spends: Vec<SpendDescription>,
+    pub shielded_outputs: Vec<OutputDescription>,
+    pub join_splits: Vec<JoinSplit>,
+    pub join_split_pubkey: Option<[u8; 32]>,
+    pub join_split_sig: Option<Vec<u8>>,
+    pub binding_sig: Option<Vec<u8>>,
+}
+
+impl Transaction {
+    pub fn txid(&self) -> Txid {
+        Txid::hash(&encode::serialize(self))
+    }
+
+    pub fn is_sapling(&self) -> bool {
+        self.overwintered && self.version >= 4 && self.version_group_id == SAPLING_VERSION_GROUP_ID
+    }
+
+    pub fn is_coinbase(&self) -> bool {
+        self.input.len() == 1 && self.input[0].previous_output.is_null()
+    }
+
+    /// The hash that transparent input `input` signs, as defined for Sapling transactions in
+    /// ZIP 243. `script_code` is the script of the spent output, or the redeem script when it is
+    /// a P2SH output, and `amount` its value in satoshis.
+    ///
+    /// Returns None for transactions that are not Sapling transactions, or if there is no such
+    /// input.
+    pub fn signature_hash(
+        &self,
+        input: usize,
+        script_code: &Script,
+        amount: u64,
+        sighash_type: u32,
+        consensus_branch_id: u32,
+    ) -> Option<[u8; 32]> {
+        let txin = self.input.get(input)?;
+        if !self.is_sapling() {
+            return None;
+        }
+
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let base_type = sighash_type & 0x1f;
+
+        let hash_prevouts = match anyone_can_pay {
+            false => hash_all(
+                b"ZcashPrevoutHash",
+                self.input.iter().map(|i| &i.previous_output),
+            ),
+            true => [0; 32],
+        };
+        let hash_sequence =
+            match anyone_can_pay || base_type == SIGHASH_SINGLE || base_type == SIGHASH_NONE {
+                false => hash_all(b"ZcashSequencHash", self.input.iter().map(|i| &i.sequence)),
+                true => [0; 32],
+            };
+        let hash_outputs = match self.output.get(input) {
+            _ if base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE => {
+                hash_all(b"ZcashOutputsHash", self.output.iter())
+            }
+            Some(output) if base_type == SIGHASH_SINGLE => hash_all(b"ZcashOutputsHash", [output]),
+            _ => [0; 32],
+        };
+        let hash_join_splits = match self.join_splits.is_empty() {
+            false => {
+                let mut data = vec![];
+                for join_split in self.join_splits.iter() {
+                    data.extend(encode::serialize(join_split));
+                }
+                data.extend(self.join_split_pubkey.unwrap_or_default());
+                blake2b_256(b"ZcashJSplitsHash", &data)
+            }
+            true => [0; 32],
+        };
+        let hash_spends = match self.shielded_spends.is_empty() {
+            false => {
+                let mut data = vec![];
+                for spend in self.shielded_spends.iter() {
+                    data.extend(spend.cv);
+                    data.extend(spend.anchor);
+                    data.extend(spend.nullifier);
+                    data.extend(spend.rk);
+                    data.extend(&spend.zkproof);
+                }
+                blake2b_256(b"ZcashSSpendsHash", &data)
+            }
+            true => [0; 32],
+        };
+        let hash_shielded_outputs = match self.shielded_outputs.is_empty() {
+            false => hash_all(b"ZcashSOutputHash", self.shielded_outputs.iter()),
+            true => [0; 32],
+        };
+
+        let mut preimage = vec![];
+        preimage.extend((self.version | OVERWINTERED_FLAG).to_le_bytes());
+        preimage.extend(self.version_group_id.to_le_bytes());
+        preimage.extend(hash_prevouts);
+        preimage.extend(hash_sequence);
+        preimage.extend(hash_outputs);
+        preimage.extend(hash_join_splits);
+        preimage.extend(hash_spends);
+        preimage.extend(hash_shielded_outputs);
+        preimage.extend(self.lock_time.to_le_bytes());
+        preimage.extend(self.expiry_height.to_le_bytes());
+        preimage.extend(self.value_balance.to_le_bytes());
+        preimage.extend(sighash_type.to_le_bytes());
+        preimage.extend(encode::serialize(&txin.previous_output));
+        preimage.extend(encode::serialize(script_code));
+        preimage.extend(amount.to_le_bytes());
+        preimage.extend(txin.sequence.0.to_le_bytes());
+
+        let mut personal = *b"ZcashSigHash\0\0\0\0";
+        personal[12..].copy_from_slice(&consensus_branch_id.to_le_bytes());
+
+        Some(blake2b_256(&personal, &preimage))
+    }
+
+    fn proof_size(&self) -> usize {
+        match self.is_sapling() {
+            true => GROTH_PROOF_SIZE,
+            false => PHGR_PROOF_SIZE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub version: i32,
+    pub prev_blockhash: BlockHash,
+    pub merkle_root: TxMerkleNode,
+    pub final_sapling_root: [u8; 32],
+    pub time: u32,
+    pub bits: u32,
+    pub nonce: [u8; 32],
+    pub solution: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub txdata: Vec<Transaction>,
+}
+
+/// Decodes a hex string as returned by the daemon, failing if any bytes are left over.
+pub fn deserialize_hex<T: Decodable>(hex: &str) -> Result<T, encode::Error> {
+    let bytes = hex::decode(hex).map_err(|_| encode::Error::ParseFailed("invalid hex"))?;
+
+    encode::deserialize(&bytes)
+}
+
+fn read_bytes<R: io::Read + ?Sized>(r: &mut R, len: usize) -> Result<Vec<u8>, encode::Error> {
+    let mut bytes = vec![0; len];
+    r.read_exact(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn read_count<R: io::Read + ?Sized>(r: &mut R) -> Result<usize, encode::Error> {
+    let count = VarInt::consensus_decode_from_finite_reader(r)?.0;
+    if count > encode::MAX_VEC_SIZE as u64 {
+        return Err(encode::Error::ParseFailed("too many elements"));
+    }
+
+    Ok(count as usize)
+}
+
+fn write_bytes<W: io::Write + ?Sized>(w: &mut W, bytes: &[u8]) -> Result<usize, io::Error> {
+    w.write_all(bytes)?;
+
+    Ok(bytes.len())
+}
+
+/// Hashes the serializations of `items`, one after the other.
+fn hash_all<'a, T: Encodable + 'a, I: IntoIterator<Item = &'a T>>(
+    personal: &[u8; 16],
+    items: I,
+) -> [u8; 32] {
+    let mut data = vec![];
+    for item in items {
+        data.extend(encode::serialize(item));
+    }
+
+    blake2b_256(personal, &data)
+}
+
+impl Decodable for SpendDescription {
+    fn consensus_decode<R: io::Read + ?Sized>(r: &mut R) -> Result<Self, encode::Error> {
+        Ok(SpendDescription {
+            cv: Decodable::consensus_decode(r)?,
+            anchor: Decodable::consensus_decode(r)?,
+            nullifier: Decodable::consensus_decode(r)?,
+            rk: Decodable::consensus_decode(r)?,
+            zkproof: read_bytes(r, GROTH_PROOF_SIZE)?,
+            spend_auth_sig: read_bytes(r, 64)?,
+        })
+    }
+}
+
+impl Encodable for SpendDescription {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.cv.consensus_encode(w)?;
+        len += self.anchor.consensus_encode(w)?;
+        len += self.nullifier.consensus_encode(w)?;
+        len += self.rk.consensus_encode(w)?;
+        len += write_bytes(w, &self.zkproof)?;
+        len += write_bytes(w, &self.spend_auth_sig)?;
+        Ok(len)
+    }
+}
+
+impl Decodable for OutputDescription {
+    fn consensus_decode<R: io::Read + ?Sized>(r: &mut R) -> Result<Self, encode::Error> {
+        Ok(OutputDescription {
+            cv: Decodable::consensus_decode(r)?,
+            cmu: Decodable::consensus_decode(r)?,
+            ephemeral_key: Decodable::consensus_decode(r)?,
+            enc_ciphertext: read_bytes(r, ENC_CIPHERTEXT_SIZE)?,
+            out_ciphertext: read_bytes(r, OUT_CIPHERTEXT_SIZE)?,
+            zkproof: read_bytes(r, GROTH_PROOF_SIZE)?,
+        })
+    }
+}
+
+impl Encodable for OutputDescription {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.cv.consensus_encode(w)?;
+        len += self.cmu.consensus_encode(w)?;
+        len += self.ephemeral_key.consensus_encode(w)?;
+        len += write_bytes(w, &self.enc_ciphertext)?;
+        len += write_bytes(w, &self.out_ciphertext)?;
+        len += write_bytes(w, &self.zkproof)?;
+        Ok(len)
+    }
+}
+
+impl JoinSplit {
+    fn decode<R: io::Read + ?Sized>(r: &mut R, proof_size: usize) -> Result<Self, encode::Error> {
+        Ok(JoinSplit {
+            vpub_old: Decodable::consensus_decode(r)?,
+            vpub_new: Decodable::consensus_decode(r)?,
+            anchor: Decodable::consensus_decode(r)?,
+            nullifiers: [
+                Decodable::consensus_decode(r)?,
+                Decodable::consensus_decode(r)?,
+            ],
+            commitments: [
+                Decodable::consensus_decode(r)?,
+                Decodable::consensus_decode(r)?,
+            ],
+            ephemeral_key: Decodable::consensus_decode(r)?,
+            random_seed: Decodable::consensus_decode(r)?,
+            macs: [
+                Decodable::consensus_decode(r)?,
+                Decodable::consensus_decode(r)?,
+            ],
+            proof: read_bytes(r, proof_size)?,
+            ciphertexts: [
+                read_bytes(r, NOTE_CIPHERTEXT_SIZE)?,
+                read_bytes(r, NOTE_CIPHERTEXT_SIZE)?,
+            ],
+        })
+    }
+}
+
+impl Encodable for JoinSplit {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.vpub_old.consensus_encode(w)?;
+        len += self.vpub_new.consensus_encode(w)?;
+        len += self.anchor.consensus_encode(w)?;
+        for nullifier in self.nullifiers.iter() {
+            len += nullifier.consensus_encode(w)?;
+        }
+        for commitment in self.commitments.iter() {
+            len += commitment.consensus_encode(w)?;
+        }
+        len += self.ephemeral_key.consensus_encode(w)?;
+        len += self.random_seed.consensus_encode(w)?;
+        for mac in self.macs.iter() {
+            len += mac.consensus_encode(w)?;
+        }
+        len += write_bytes(w, &self.proof)?;
+        for ciphertext in self.ciphertexts.iter() {
+            len += write_bytes(w, ciphertext)?;
+        }
+        Ok(len)
+    }
+}
+
+impl Decodable for Transaction {
+    fn consensus_decode_from_finite_reader<R: io::Read + ?Sized>(
+        r: &mut R,
+    ) -> Result<Self, encode::Error> {
+        let header = u32::consensus_decode_from_finite_reader(r)?;
+        let overwintered = header & OVERWINTERED_FLAG != 0;
+        let version = header & !OVERWINTERED_FLAG;
+
+        let mut tx = Transaction {
+            overwintered,
+            version,
+            version_group_id: 0,
+            input: vec![],
+            output: vec![],
+            lock_time: 0,
+            expiry_height: 0,
+            value_balance: 0,
+            shielded_spends: vec![],
+            shielded_outputs: vec![],
+            join_splits: vec![],
+            join_split_pubkey: None,
+            join_split_sig: None,
+            binding_sig: None,
+        };
+
+        if overwintered {
+            tx.version_group_id = u32::consensus_decode_from_finite_reader(r)?;
+            if tx.version_group_id != OVERWINTER_VERSION_GROUP_ID
+                && tx.version_group_id != SAPLING_VERSION_GROUP_ID
+            {
+                return Err(encode::Error::ParseFailed("unknown version group id"));
+            }
+        }
+
+        tx.input = Decodable::consensus_decode_from_finite_reader(r)?;
+        tx.output = Decodable::consensus_decode_from_finite_reader(r)?;
+        tx.lock_time = u32::consensus_decode_from_finite_reader(r)?;
+        if overwintered {
+            tx.expiry_height = u32::consensus_decode_from_finite_reader(r)?;
+        }
+
+        if tx.is_sapling() {
+            tx.value_balance = i64::consensus_decode_from_finite_reader(r)?;
+            for _ in 0..read_count(r)? {
+                tx.shielded_spends
+                    .push(SpendDescription::consensus_decode(r)?);
+            }
+            for _ in 0..read_count(r)? {
+                tx.shielded_outputs
+                    .push(OutputDescription::consensus_decode(r)?);
+            }
+        }
+
+        if tx.version >= 2 {
+            let proof_size = tx.proof_size();
+            for _ in 0..read_count(r)? {
+                tx.join_splits.push(JoinSplit::decode(r, proof_size)?);
+            }
+            if !tx.join_splits.is_empty() {
+                tx.join_split_pubkey = Some(Decodable::consensus_decode(r)?);
+                tx.join_split_sig = Some(read_bytes(r, 64)?);
+            }
+        }
+
+        if tx.is_sapling() && !(tx.shielded_spends.is_empty() && tx.shielded_outputs.is_empty()) {
+            tx.binding_sig = Some(read_bytes(r, 64)?);
+        }
+
+        Ok(tx)
+    }
+}
+
+impl Encodable for Transaction {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        let header = match self.overwintered {
+            true => self.version | OVERWINTERED_FLAG,
+            false => self.version,
+        };
+        len += header.consensus_encode(w)?;
+        if self.overwintered {
+            len += self.version_group_id.consensus_encode(w)?;
+        }
+
+        len += self.input.consensus_encode(w)?;
+        len += self.output.consensus_encode(w)?;
+        len += self.lock_time.consensus_encode(w)?;
+        if self.overwintered {
+            len += self.expiry_height.consensus_encode(w)?;
+        }
+
+        if self.is_sapling() {
+            len += self.value_balance.consensus_encode(w)?;
+            len += VarInt(self.shielded_spends.len() as u64).consensus_encode(w)?;
+            for spend in self.shielded_spends.iter() {
+                len += spend.consensus_encode(w)?;
+            }
+            len += VarInt(self.shielded_outputs.len() as u64).consensus_encode(w)?;
+            for output in self.shielded_outputs.iter() {
+                len += output.consensus_encode(w)?;
+            }
+        }
+
+        if self.version >= 2 {
+            len += VarInt(self.join_splits.len() as u64).consensus_encode(w)?;
+            for join_split in self.join_splits.iter() {
+                len += join_split.consensus_encode(w)?;
+            }
+            if let (Some(pubkey), Some(sig)) = (&self.join_split_pubkey, &self.join_split_sig) {
+                len += pubkey.consensus_encode(w)?;
+                len += write_bytes(w, sig)?;
+            }
+        }
+
+        if let Some(binding_sig) = &self.binding_sig {
+            len += write_bytes(w, binding_sig)?;
+        }
+
+        Ok(len)
+    }
+}
+
+impl Decodable for BlockHeader {
+    fn consensus_decode_from_finite_reader<R: io::Read + ?Sized>(
+        r: &mut R,
+    ) -> Result<Self, encode::Error> {
+        Ok(BlockHeader {
+            version: Decodable::consensus_decode_from_finite_reader(r)?,
+            prev_blockhash: Decodable::consensus_decode_from_finite_reader(r)?,
+            merkle_root: Decodable::consensus_decode_from_finite_reader(r)?,
+            final_sapling_root: Decodable::consensus_decode_from_finite_reader(r)?,
+            time: Decodable::consensus_decode_from_finite_reader(r)?,
+            bits: Decodable::consensus_decode_from_finite_reader(r)?,
+            nonce: Decodable::consensus_decode_from_finite_reader(r)?,
+            solution: Decodable::consensus_decode_from_finite_reader(r)?,
+        })
+    }
+}
+
+impl Encodable for BlockHeader {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = 0;
+        len += self.version.consensus_encode(w)?;
+        len += self.prev_blockhash.consensus_encode(w)?;
+        len += self.merkle_root.consensus_encode(w)?;
+        len += self.final_sapling_root.consensus_encode(w)?;
+        len += self.time.consensus_encode(w)?;
+        len += self.bits.consensus_encode(w)?;
+        len += self.nonce.consensus_encode(w)?;
+        len += self.solution.consensus_encode(w)?;
+        Ok(len)
+    }
+}
+
+impl Decodable for Block {
+    fn consensus_decode_from_finite_reader<R: io::Read + ?Sized>(
+        r: &mut R,
+    ) -> Result<Self, encode::Error> {
+        let header = BlockHeader::consensus_decode_from_finite_reader(r)?;
+        let mut txdata = vec![];
+        for _ in 0..read_count(r)? {
+            txdata.push(Transaction::consensus_decode_from_finite_reader(r)?);
+        }
+
+        Ok(Block { header, txdata })
+    }
+}
+
+impl Encodable for Block {
+    fn consensus_encode<W: io::Write + ?Sized>(&self, w: &mut W) -> Result<usize, io::Error> {
+        let mut len = self.header.consensus_encode(w)?;
+        len += VarInt(self.txdata.len() as u64).consensus_encode(w)?;
+        for tx in self.txdata.iter() {
+            len += tx.consensus_encode(w)?;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{OutPoint, Script, Sequence, Witness};
+
+    fn sapling_tx() -> Transaction {
+        Transaction {
+            overwintered: true,
+            version: 4,
+            version_group_id: SAPLING_VERSION_GROUP_ID,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::from_inner([1; 32]), 2),
+                script_sig: Script::from(vec![0x51]),
+                sequence: Sequence::MAX,
+                witness: Witness::default(),
+            }],
+            output: vec![TxOut {
+                value: 100_000_000,
+                script_pubkey: Script::from(vec![0x76, 0xa9]),
+            }],
+            lock_time: 0,
+            expiry_height: 2_000_020,
+            value_balance: -50_000,
+            shielded_spends: vec![],
+            shielded_outputs: vec![OutputDescription {
+                cv: [3; 32],
+                cmu: [4; 32],
+                ephemeral_key: [5; 32],
+                enc_ciphertext: vec![6; ENC_CIPHERTEXT_SIZE],
+                out_ciphertext: vec![7; OUT_CIPHERTEXT_SIZE],
+                zkproof: vec![8; GROTH_PROOF_SIZE],
+            }],
+            join_splits: vec![],
+            join_split_pubkey: None,
+            join_split_sig: None,
+            binding_sig: Some(vec![9; 64]),
+        }
+    }
+
+    // Transactions from before Overwinter have the Bitcoin format, so a recorded Bitcoin mainnet
+    // transaction checks the transparent fields and the txid against real data.
+    #[test]
+    fn decodes_recorded_v1_transaction() {
+        let hex = "0100000001a15d57094aa7a21a28cb20b59aab8fc7d1149a3bdbcddba9c622e4f5f6a99ece010000006c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52ffffffff0100e1f505000000001976a9140389035a9225b3839e2bbf32d826a1e222031fd888ac00000000";
+        let tx: Transaction = deserialize_hex(hex).unwrap();
+
+        assert!(!tx.overwintered);
+        assert_eq!(tx.version, 1);
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.output.len(), 1);
+        assert_eq!(
+            tx.txid().to_string(),
+            "a6eab3c14ab5272a58a5ba91505ba1a4b6d7a3a9fcbd187b6cd99a7b6d548cb7"
+        );
+        assert_eq!(hex::encode(encode::serialize(&tx)), hex);
+    }
+
+    #[test]
+    fn roundtrips_sapling_block() {
+        let tx = sapling_tx();
+        let bytes = encode::serialize(&tx);
+        // header and version group id
+        assert_eq!(
+            &bytes[..8],
+            &[0x04, 0x00, 0x00, 0x80, 0x85, 0x20, 0x2f, 0x89]
+        );
+        assert_eq!(encode::deserialize::<Transaction>(&bytes).unwrap(), tx);
+
+        let block = Block {
+            header: BlockHeader {
+                version: 65540,
+                prev_blockhash: BlockHash::from_inner([10; 32]),
+                merkle_root: TxMerkleNode::from_inner([11; 32]),
+                final_sapling_root: [12; 32],
+                time: 1_700_000_000,
+                bits: 0x1b02_8f4c,
+                nonce: [13; 32],
+                solution: vec![14; 1344],
+            },
+            txdata: vec![tx.clone(), tx],
+        };
+        let hex = hex::encode(encode::serialize(&block));
+        let decoded: Block = deserialize_hex(&hex).unwrap();
+        assert_eq!(decoded, block);
+        assert_eq!(decoded.txdata[1].txid(), block.txdata[0].txid());
+
+        assert!(deserialize_hex::<Block>(&hex[..hex.len() - 2]).is_err());
+    }
+}
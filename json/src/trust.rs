@@ -0,0 +1,160 @@
+//! The wallet's trust ratings of currencies and identities, as used by `getcurrencytrust`,
+//! `setcurrencytrust`, `getidentitytrust` and `setidentitytrust`.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::vrsc::Address;
+
+/// How the wallet treats currencies or identities according to their rating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum TrustMode {
+    NoRestriction,
+    /// Only currencies or identities rated as approved.
+    ApprovedOnly,
+    /// Everything except what is rated as blocked.
+    BlockBlocked,
+    Unknown(u8),
+}
+
+impl From<u8> for TrustMode {
+    fn from(mode: u8) -> Self {
+        match mode {
+            0 => TrustMode::NoRestriction,
+            1 => TrustMode::ApprovedOnly,
+            2 => TrustMode::BlockBlocked,
+            other => TrustMode::Unknown(other),
+        }
+    }
+}
+
+impl From<TrustMode> for u8 {
+    fn from(mode: TrustMode) -> Self {
+        match mode {
+            TrustMode::NoRestriction => 0,
+            TrustMode::ApprovedOnly => 1,
+            TrustMode::BlockBlocked => 2,
+            TrustMode::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "u32", into = "u32")]
+pub enum TrustLevel {
+    Unset,
+    Blocked,
+    Approved,
+    Unknown(u32),
+}
+
+impl From<u32> for TrustLevel {
+    fn from(level: u32) -> Self {
+        match level {
+            0 => TrustLevel::Unset,
+            1 => TrustLevel::Blocked,
+            2 => TrustLevel::Approved,
+            other => TrustLevel::Unknown(other),
+        }
+    }
+}
+
+impl From<TrustLevel> for u32 {
+    fn from(level: TrustLevel) -> Self {
+        match level {
+            TrustLevel::Unset => 0,
+            TrustLevel::Blocked => 1,
+            TrustLevel::Approved => 2,
+            TrustLevel::Unknown(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Rating {
+    pub version: u32,
+    pub trustlevel: TrustLevel,
+    /// Ratings by other identities, by rating identity.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub ratingsmap: HashMap<Address, Value>,
+}
+
+impl Rating {
+    pub fn new(trustlevel: TrustLevel) -> Self {
+        Rating {
+            version: 1,
+            trustlevel,
+            ratingsmap: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetCurrencyTrustResult {
+    #[serde(default)]
+    pub setratings: HashMap<Address, Rating>,
+    pub currencytrustmode: TrustMode,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetIdentityTrustResult {
+    #[serde(default)]
+    pub setratings: HashMap<Address, Rating>,
+    pub identitytrustmode: TrustMode,
+}
+
+/// Changes to the wallet's trust settings. Fields that are not set are left unchanged.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SetTrustRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clearall: Option<bool>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    setratings: HashMap<Address, Rating>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    removeratings: Vec<Address>,
+    #[serde(skip_serializing_if = "Option::is_none", flatten)]
+    trustmode: Option<TrustModeSetting>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+enum TrustModeSetting {
+    #[serde(rename = "currencytrustmode")]
+    Currency(TrustMode),
+    #[serde(rename = "identitytrustmode")]
+    Identity(TrustMode),
+}
+
+impl SetTrustRequest {
+    pub fn new() -> Self {
+        SetTrustRequest::default()
+    }
+
+    /// Removes all ratings before applying the rest of this request.
+    pub fn clear_all(mut self) -> Self {
+        self.clearall = Some(true);
+        self
+    }
+
+    pub fn rate(mut self, id: Address, rating: Rating) -> Self {
+        self.setratings.insert(id, rating);
+        self
+    }
+
+    pub fn remove(mut self, id: Address) -> Self {
+        self.removeratings.push(id);
+        self
+    }
+
+    /// Only takes effect in `setcurrencytrust`.
+    pub fn currency_trust_mode(mut self, mode: TrustMode) -> Self {
+        self.trustmode = Some(TrustModeSetting::Currency(mode));
+        self
+    }
+
+    /// Only takes effect in `setidentitytrust`.
+    pub fn identity_trust_mode(mut self, mode: TrustMode) -> Self {
+        self.trustmode = Some(TrustModeSetting::Identity(mode));
+        self
+    }
+}